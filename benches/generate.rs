@@ -1,10 +1,41 @@
+#![cfg(feature = "nightly")]
 #![feature(test)]
 extern crate test;
 
-use identicon::Identicon;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use identicon::{Identicon, Renderer};
 use md5::{Digest, Md5};
 use test::Bencher;
 
+// Counts allocations, so the batch benchmarks can report how many they make.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// Runs `f` once and prints how many allocations it made.
+fn report_allocations(name: &str, mut f: impl FnMut()) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    let count = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    eprintln!("{}: {} allocations", name, count);
+}
+
 #[bench]
 fn generate(x: &mut Bencher) {
     let input = "42".as_bytes();
@@ -12,3 +43,49 @@ fn generate(x: &mut Bencher) {
     let identicon = Identicon::new(&bytes);
     x.iter(|| identicon.image());
 }
+
+#[bench]
+fn generate_2048(x: &mut Bencher) {
+    let bytes = Md5::digest("42".as_bytes());
+    let identicon = Identicon::new(&bytes).size(2048);
+    x.iter(|| identicon.image());
+}
+
+#[cfg(feature = "rayon")]
+#[bench]
+fn generate_2048_parallel(x: &mut Bencher) {
+    let bytes = Md5::digest("42".as_bytes());
+    let identicon = Identicon::new(&bytes).size(2048);
+    x.iter(|| identicon.image_parallel());
+}
+
+// 10k small icons from distinct hashes, allocating a new image for each.
+#[bench]
+fn batch_image(x: &mut Bencher) {
+    let hashes: Vec<_> = (0..10_000u32)
+        .map(|i| Md5::digest(&i.to_le_bytes()))
+        .collect();
+    let batch = || {
+        for hash in &hashes {
+            test::black_box(Identicon::new(hash).size(64).image());
+        }
+    };
+    report_allocations("batch_image", batch);
+    x.iter(batch);
+}
+
+// The same, reusing one image and its buffers for the cells and samples.
+#[bench]
+fn batch_renderer(x: &mut Bencher) {
+    let hashes: Vec<_> = (0..10_000u32)
+        .map(|i| Md5::digest(&i.to_le_bytes()))
+        .collect();
+    let mut renderer = Renderer::new();
+    let mut batch = || {
+        for hash in &hashes {
+            test::black_box(renderer.render(&Identicon::new(hash).size(64)));
+        }
+    };
+    report_allocations("batch_renderer", &mut batch);
+    x.iter(batch);
+}
@@ -0,0 +1,126 @@
+//! The algorithm behind Ethereum's "blockies" avatars, for `Mode::Blockies`.
+//!
+//! See: <https://github.com/ethereum/blockies/blob/master/blockies.js>
+
+use alloc::vec::Vec;
+
+use crate::HSL;
+
+/// A blockies palette and grid, generated from a seed string.
+pub struct Blockies {
+    pub color: HSL,
+    pub background: HSL,
+    pub spot: HSL,
+    /// `size * size` cells in row-major order: 0 for the background, 1 for
+    /// `color`, and 2 for `spot`.
+    pub cells: Vec<u8>,
+}
+
+impl Blockies {
+    pub fn new(seed: &[u8], size: usize) -> Blockies {
+        let mut rng = Xorshift::new(seed);
+        let color = rng.color();
+        let background = rng.color();
+        let spot = rng.color();
+
+        // The left half is random, and mirrored onto the right without a
+        // shared center column.
+        let generated = size.div_ceil(2);
+        let mut cells = Vec::with_capacity(size * size);
+        for _ in 0..size {
+            let start = cells.len();
+            for _ in 0..generated {
+                // `rand()` runs up to 2.0, so this can also give 3 and 4,
+                // which blockies paints as spots.
+                cells.push((rng.rand() * 2.3) as u8);
+            }
+            for col in generated..size {
+                cells.push(cells[start + size - 1 - col]);
+            }
+        }
+        for cell in cells.iter_mut() {
+            *cell = (*cell).min(2);
+        }
+
+        Blockies {
+            color,
+            background,
+            spot,
+            cells,
+        }
+    }
+}
+
+// The xorshift generator blockies seeds from its string, with JavaScript's
+// 32-bit integer arithmetic.
+struct Xorshift([i32; 4]);
+
+impl Xorshift {
+    fn new(seed: &[u8]) -> Xorshift {
+        let mut state = [0i32; 4];
+        for (ix, &byte) in seed.iter().enumerate() {
+            let s = &mut state[ix % 4];
+            *s = (*s << 5).wrapping_sub(*s).wrapping_add(byte as i32);
+        }
+        Xorshift(state)
+    }
+
+    // A float from 0.0 up to, but not including, 2.0.
+    fn rand(&mut self) -> f64 {
+        let [x, y, z, w] = self.0;
+        let t = x ^ (x << 11);
+        let next = w ^ (w >> 19) ^ t ^ (t >> 8);
+        self.0 = [y, z, w, next];
+        next as u32 as f64 / (1u64 << 31) as f64
+    }
+
+    // A color as CSS would read blockies' `hsl()` string, clamping the
+    // saturation and luminance to 100%.
+    fn color(&mut self) -> HSL {
+        let hue = (self.rand() * 360.0) as u32 % 360;
+        let sat = self.rand() * 60.0 + 40.0;
+        let lum = (self.rand() + self.rand() + self.rand() + self.rand()) * 25.0;
+        HSL::new(hue as f32, sat.min(100.0) as f32, lum.min(100.0) as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Blockies;
+
+    // Expected values from blockies.js for the same seeds.
+    #[test]
+    fn it_matches_blockies_js() {
+        let blockies = Blockies::new(b"0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359", 8);
+        let rows = [
+            "10000001", "00000000", "11000011", "12111121", "01011010", "02122120", "02000020",
+            "10211201",
+        ];
+        let cells: Vec<u8> = rows.concat().bytes().map(|b| b - b'0').collect();
+        assert_eq!(cells, blockies.cells);
+
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        let colors = [
+            (blockies.color, (40.0, 95.56087, 53.072535)),
+            (blockies.background, (62.0, 84.17103, 56.513137)),
+            (blockies.spot, (244.0, 90.81825, 69.22266)),
+        ];
+        for (color, (hue, sat, lum)) in colors {
+            assert!(close(hue, color.hue) && close(sat, color.sat) && close(lum, color.lum));
+        }
+
+        let blockies = Blockies::new(b"hello", 8);
+        assert_eq!(1.0, blockies.color.hue);
+        assert_eq!(&[0, 1, 0, 0, 0, 0, 1, 0], &blockies.cells[..8]);
+    }
+
+    #[test]
+    fn it_mirrors_odd_sizes() {
+        let blockies = Blockies::new(b"hello", 5);
+        assert_eq!(25, blockies.cells.len());
+        for row in blockies.cells.chunks(5) {
+            assert_eq!((row[0], row[1]), (row[4], row[3]));
+            assert!(row.iter().all(|&cell| cell <= 2));
+        }
+    }
+}
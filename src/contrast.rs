@@ -0,0 +1,89 @@
+//! WCAG 2 contrast between colors.
+//!
+//! See: <https://www.w3.org/TR/WCAG21/#dfn-relative-luminance>
+
+use crate::Rgb;
+
+/// The contrast ratio between two colors, from 1.0 for the same luminance up
+/// to 21.0 for black on white. The order doesn't matter.
+pub fn contrast_ratio(a: Rgb<u8>, b: Rgb<u8>) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+// How bright a color looks, from 0.0 for black to 1.0 for white.
+pub fn relative_luminance(color: Rgb<u8>) -> f32 {
+    let Rgb([r, g, b]) = color;
+    0.2126 * LINEAR[r as usize] + 0.7152 * LINEAR[g as usize] + 0.0722 * LINEAR[b as usize]
+}
+
+// Each sRGB channel value converted to linear light. A table rather than
+// `powf`, which needs `std`.
+#[rustfmt::skip]
+pub const LINEAR: [f32; 256] = [
+    0.0, 0.00030353, 0.00060705, 0.00091058, 0.00121411, 0.00151763, 0.00182116, 0.002124689,
+    0.00242822, 0.002731743, 0.0030353, 0.003346536, 0.003676507, 0.00402472, 0.00439144,
+    0.004776953, 0.005181517, 0.005605392, 0.00604883, 0.006512091, 0.0069954, 0.00749903,
+    0.00802319, 0.00856813, 0.00913406, 0.00972122, 0.01032982, 0.01096009, 0.01161224,
+    0.01228649, 0.012983033, 0.01370208, 0.01444384, 0.01520851, 0.01599629, 0.01680738,
+    0.01764195, 0.0185002, 0.01938236, 0.02028856, 0.021219, 0.02217389, 0.02315337, 0.02415763,
+    0.0251869, 0.02624122, 0.02732089, 0.028426, 0.02955684, 0.03071344, 0.03189603, 0.03310477,
+    0.03433981, 0.03560131, 0.0368894, 0.03820437, 0.03954624, 0.040915, 0.0423114, 0.043735,
+    0.0451862, 0.04666509, 0.04817183, 0.04970657, 0.05126946, 0.05286065, 0.05448028,
+    0.0561285, 0.0578054, 0.05951124, 0.06124605, 0.06301001, 0.06480327, 0.0666259, 0.0684782,
+    0.07036009, 0.0722719, 0.0742136, 0.0761854, 0.0781874, 0.0802198, 0.0822827, 0.0843762,
+    0.0865005, 0.0886556, 0.0908417, 0.09305897, 0.0953075, 0.0975873, 0.09989873, 0.1022417,
+    0.10461649, 0.10702311, 0.1094617, 0.1119324, 0.11443537, 0.11697067, 0.1195384, 0.12213878,
+    0.1247718, 0.1274377, 0.1301365, 0.1328683, 0.1356333, 0.1384316, 0.1412633, 0.1441285,
+    0.1470273, 0.1499598, 0.1529261, 0.1559265, 0.1589608, 0.1620294, 0.165132, 0.168269,
+    0.1714411, 0.174647, 0.1778884, 0.1811642, 0.184475, 0.1878208, 0.1912017, 0.1946178,
+    0.1980693, 0.2015563, 0.2050787, 0.2086369, 0.2122308, 0.215861, 0.219526, 0.223228,
+    0.2269659, 0.2307401, 0.2345506, 0.2383976, 0.2422811, 0.2462013, 0.2501583, 0.254152,
+    0.2581829, 0.2622507, 0.266356, 0.270498, 0.274677, 0.2788943, 0.2831487, 0.2874408,
+    0.2917706, 0.2961383, 0.3005438, 0.304987, 0.3094689, 0.3139887, 0.3185468, 0.323143,
+    0.327778, 0.3324515, 0.3371636, 0.3419144, 0.3467041, 0.351533, 0.3564001, 0.361307,
+    0.366253, 0.371238, 0.3762621, 0.381326, 0.3864294, 0.3915725, 0.3967552, 0.4019778,
+    0.40724, 0.412543, 0.4178851, 0.4232677, 0.42869, 0.4341536, 0.4396572, 0.445201, 0.450786,
+    0.456411, 0.46208, 0.467784, 0.4735315, 0.4793202, 0.4851499, 0.4910209, 0.496933, 0.502886,
+    0.5088813, 0.514918, 0.5209956, 0.527115, 0.533276, 0.539479, 0.5457245, 0.5520114, 0.55834,
+    0.564712, 0.5711249, 0.5775805, 0.5840784, 0.5906188, 0.5972018, 0.6038274, 0.6104956,
+    0.617207, 0.62396, 0.6307572, 0.6375968, 0.64448, 0.6514056, 0.6583748, 0.665387, 0.6724432,
+    0.679542, 0.686685, 0.6938717, 0.701102, 0.7083758, 0.715693, 0.723055, 0.7304608, 0.73791,
+    0.745404, 0.752942, 0.760525, 0.7681512, 0.775822, 0.783538, 0.791298, 0.799103, 0.8069522,
+    0.814847, 0.8227857, 0.83077, 0.8388, 0.846873, 0.854993, 0.863157, 0.871367, 0.879622,
+    0.887923, 0.896269, 0.904661, 0.9130986, 0.9215819, 0.930111, 0.938686, 0.947307, 0.955973,
+    0.964686, 0.973445, 0.982251, 0.991102, 1.0,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{contrast_ratio, relative_luminance, LINEAR};
+    use crate::Rgb;
+
+    #[test]
+    fn it_linearizes_channels() {
+        for (ix, &value) in LINEAR.iter().enumerate() {
+            let c = ix as f32 / 255.0;
+            let expected = if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            };
+            assert!((expected - value).abs() < 1e-5, "{}", ix);
+        }
+        assert!((1.0 - relative_luminance(Rgb([255, 255, 255]))).abs() < 1e-6);
+    }
+
+    #[test]
+    fn it_compares_known_pairs() {
+        let black = Rgb([0, 0, 0]);
+        let white = Rgb([255, 255, 255]);
+        assert!((21.0 - contrast_ratio(black, white)).abs() < 1e-4);
+        assert_eq!(contrast_ratio(black, white), contrast_ratio(white, black));
+        assert_eq!(1.0, contrast_ratio(white, white));
+
+        // #777 on white is just short of the 4.5 that WCAG AA asks for.
+        let ratio = contrast_ratio(Rgb([119, 119, 119]), white);
+        assert!((ratio - 4.48).abs() < 0.01, "{}", ratio);
+    }
+}
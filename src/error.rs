@@ -0,0 +1,140 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Errors that can occur while building or rendering an identicon.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum IdenticonError {
+    /// The source has fewer bytes than the color mode reads from.
+    SourceTooShort { needed: usize, got: usize },
+    /// Grids need an odd size, so that there's a center column to mirror around.
+    InvalidGridSize(u32),
+    /// A palette needs at least one color to pick from.
+    EmptyPalette,
+    /// An option outside of the range it's documented to take.
+    OutOfRange { option: &'static str, value: f32 },
+    /// The icon doesn't fit where it was asked to be drawn.
+    OutOfBounds(RenderError),
+    /// The `image` crate failed to encode the rendered image.
+    #[cfg(feature = "std")]
+    Encode(image::ImageError),
+}
+
+impl fmt::Display for IdenticonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdenticonError::SourceTooShort { needed, got } => write!(
+                f,
+                "source is too short: needed at least {} bytes, got {}",
+                needed, got
+            ),
+            IdenticonError::InvalidGridSize(n) => write!(f, "grid size must be odd, got {}", n),
+            IdenticonError::EmptyPalette => f.write_str("palette must have at least one color"),
+            IdenticonError::OutOfRange { option, value } => {
+                write!(f, "{} is out of range, got {}", option, value)
+            }
+            IdenticonError::OutOfBounds(err) => err.fmt(f),
+            #[cfg(feature = "std")]
+            IdenticonError::Encode(err) => write!(f, "could not encode image: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IdenticonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IdenticonError::Encode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<image::ImageError> for IdenticonError {
+    fn from(err: image::ImageError) -> Self {
+        IdenticonError::Encode(err)
+    }
+}
+
+impl From<RenderError> for IdenticonError {
+    fn from(err: RenderError) -> Self {
+        IdenticonError::OutOfBounds(err)
+    }
+}
+
+/// Errors from parsing a hex color string with `parse_hex`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ColorParseError {
+    /// There must be 3 or 6 digits after the optional `#`.
+    InvalidLength(usize),
+    /// A character that isn't a hex digit.
+    InvalidDigit(char),
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorParseError::InvalidLength(len) => {
+                write!(f, "hex color must have 3 or 6 digits, got {}", len)
+            }
+            ColorParseError::InvalidDigit(c) => write!(f, "invalid hex digit {:?}", c),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ColorParseError {}
+
+/// The error from parsing a `Mode` from a name it doesn't have, which it
+/// holds.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ModeParseError(pub String);
+
+impl fmt::Display for ModeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown mode {:?}, expected github, identicon-js, github-tuned, grayscale, or blockies",
+            self.0
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ModeParseError {}
+
+/// Errors from drawing into an existing image.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RenderError {
+    /// The icon, `size` pixels as `(width, height)` at `(x, y)`, doesn't fit
+    /// in the image.
+    OutOfBounds {
+        x: u32,
+        y: u32,
+        size: (u32, u32),
+        width: u32,
+        height: u32,
+    },
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::OutOfBounds {
+                x,
+                y,
+                size,
+                width,
+                height,
+            } => write!(
+                f,
+                "a {}x{} icon at ({}, {}) doesn't fit in a {}x{} image",
+                size.0, size.1, x, y, width, height
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RenderError {}
@@ -1,22 +1,95 @@
-use image::Rgb;
+use crate::{Rgb, Rgba};
 
+/// A color as hue, saturation, and luminance, with optional transparency.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct HSL {
-    hue: f32,
-    sat: f32,
-    lum: f32,
+    /// Degrees around the color wheel, from 0 to 360.
+    pub hue: f32,
+    /// Percent, from 0 to 100.
+    pub sat: f32,
+    /// Percent, from 0 to 100.
+    pub lum: f32,
+    /// Opacity, from 0.0 for fully transparent to 1.0 for opaque.
+    pub alpha: f32,
 }
 
 impl HSL {
+    /// An opaque color.
     pub fn new(hue: f32, sat: f32, lum: f32) -> HSL {
+        HSL::hsla(hue, sat, lum, 1.0)
+    }
+
+    /// A color with the given opacity.
+    pub fn hsla(hue: f32, sat: f32, lum: f32, alpha: f32) -> HSL {
         HSL {
-            hue: hue,
-            sat: sat,
-            lum: lum,
+            hue,
+            sat,
+            lum,
+            alpha,
         }
     }
 
-    // http://www.w3.org/TR/css3-color/#hsl-color
+    /// The inverse of `rgb()`, for an opaque color. Grays have no hue, so
+    /// they come back with a hue and saturation of 0.
+    // https://www.w3.org/TR/css-color-4/#rgb-to-hsl
+    pub fn from_rgb(color: Rgb<u8>) -> HSL {
+        let Rgb([r, g, b]) = color;
+        let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lum = (max + min) / 2.0;
+
+        let delta = max - min;
+        if delta == 0.0 {
+            return HSL::new(0.0, 0.0, lum * 100.0);
+        }
+
+        let sat = if lum <= 0.5 {
+            delta / (max + min)
+        } else {
+            delta / (2.0 - max - min)
+        };
+        let hue = if max == r {
+            (g - b) / delta + if g < b { 6.0 } else { 0.0 }
+        } else if max == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+
+        HSL::new(hue * 60.0, sat * 100.0, lum * 100.0)
+    }
+
+    /// Returns this color with its hue turned `degrees` around the color wheel.
+    pub fn rotate(&self, degrees: f32) -> HSL {
+        let hue = (self.hue + degrees) % 360.0;
+        let hue = if hue < 0.0 { hue + 360.0 } else { hue };
+        HSL { hue, ..*self }
+    }
+
     pub fn rgb(&self) -> Rgb<u8> {
+        let [r, g, b] = self.channels();
+        Rgb([
+            round(r * 255.0) as u8,
+            round(g * 255.0) as u8,
+            round(b * 255.0) as u8,
+        ])
+    }
+
+    /// Like `rgb()`, with 16 bits per channel instead of rounding to 8.
+    pub fn rgb16(&self) -> Rgb<u16> {
+        let [r, g, b] = self.channels();
+        Rgb([
+            round(r * 65535.0) as u16,
+            round(g * 65535.0) as u16,
+            round(b * 65535.0) as u16,
+        ])
+    }
+
+    // The red, green, and blue channels, from 0.0 to 1.0.
+    // http://www.w3.org/TR/css3-color/#hsl-color
+    fn channels(&self) -> [f32; 3] {
         let hue = self.hue / 360.0;
         let sat = self.sat / 100.0;
         let lum = self.lum / 100.0;
@@ -31,12 +104,14 @@ impl HSL {
         let r = HSL::hue_to_rgb(a, b, hue + 1.0 / 3.0);
         let g = HSL::hue_to_rgb(a, b, hue);
         let b = HSL::hue_to_rgb(a, b, hue - 1.0 / 3.0);
+        [r, g, b]
+    }
 
-        Rgb([
-            (r * 255.0).round() as u8,
-            (g * 255.0).round() as u8,
-            (b * 255.0).round() as u8,
-        ])
+    /// Like `rgb()`, with the alpha carried through as the fourth channel.
+    pub fn rgba(&self) -> Rgba<u8> {
+        let Rgb([r, g, b]) = self.rgb();
+        let alpha = self.alpha.clamp(0.0, 1.0);
+        Rgba([r, g, b, round(alpha * 255.0) as u8])
     }
 
     fn hue_to_rgb(a: f32, b: f32, hue: f32) -> f32 {
@@ -64,10 +139,48 @@ impl HSL {
     }
 }
 
+/// Rounds half away from zero, like `f32::round`.
+#[cfg(feature = "std")]
+pub fn round(x: f32) -> f32 {
+    x.round()
+}
+
+/// Rounds half away from zero, like `f32::round`, which needs `std`.
+#[cfg(not(feature = "std"))]
+pub fn round(x: f32) -> f32 {
+    round_half_away(x)
+}
+
+#[cfg_attr(feature = "std", allow(dead_code))]
+fn round_half_away(x: f32) -> f32 {
+    // Both the truncation and the subtraction are exact, so this only ever
+    // rounds once.
+    let trunc = x as i64 as f32;
+    let fract = x - trunc;
+    if fract >= 0.5 {
+        trunc + 1.0
+    } else if fract <= -0.5 {
+        trunc - 1.0
+    } else {
+        trunc
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::HSL;
-    use image::Rgb;
+    use super::{round, round_half_away, HSL};
+    use crate::{Rgb, Rgba};
+
+    #[test]
+    fn it_converts_to_16_bits() {
+        assert_eq!(Rgb([65535, 0, 0]), HSL::new(0.0, 100.0, 50.0).rgb16());
+        let color = HSL::new(200.0, 60.0, 45.0);
+        let (Rgb(narrow), Rgb(wide)) = (color.rgb(), color.rgb16());
+        for (&narrow, &wide) in narrow.iter().zip(&wide) {
+            assert_eq!(narrow, round(wide as f32 / 257.0) as u8);
+        }
+        assert!(wide.iter().any(|&c| c % 257 != 0));
+    }
 
     #[test]
     fn it_converts_black() {
@@ -103,4 +216,53 @@ mod tests {
         let rgb = HSL::new(240.0, 100.0, 50.0).rgb();
         assert_eq!(blue, rgb);
     }
+
+    #[test]
+    fn it_converts_with_alpha() {
+        let rgba = HSL::hsla(0.0, 100.0, 50.0, 0.5).rgba();
+        assert_eq!(Rgba([255, 0, 0, 128]), rgba);
+
+        let opaque = HSL::new(120.0, 100.0, 50.0);
+        assert_eq!(Rgba([0, 255, 0, 255]), opaque.rgba());
+        assert_eq!(0.25, HSL::hsla(0.0, 0.0, 0.0, 0.25).rotate(90.0).alpha);
+    }
+
+    #[test]
+    fn it_converts_from_rgb() {
+        let red = HSL::from_rgb(Rgb([255, 0, 0]));
+        assert_eq!(HSL::new(0.0, 100.0, 50.0), red);
+
+        let gray = HSL::from_rgb(Rgb([128, 128, 128]));
+        assert_eq!((0.0, 0.0), (gray.hue, gray.sat));
+        assert!((gray.lum - 50.2).abs() < 0.1);
+    }
+
+    #[test]
+    fn it_round_trips_through_rgb() {
+        for hue in (0..360).step_by(15) {
+            for sat in (20..=100).step_by(20) {
+                for lum in (20..=80).step_by(15) {
+                    let hsl = HSL::new(hue as f32, sat as f32, lum as f32);
+                    let back = HSL::from_rgb(hsl.rgb());
+                    assert_eq!(hsl.rgb(), back.rgb());
+
+                    let hue_error = (hsl.hue - back.hue).abs();
+                    assert!(hue_error.min(360.0 - hue_error) < 2.0, "{:?}", back);
+                    assert!((hsl.sat - back.sat).abs() < 1.5, "{:?}", back);
+                    assert!((hsl.lum - back.lum).abs() < 0.5, "{:?}", back);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_rounds_without_std() {
+        for i in -2000..2000 {
+            let x = i as f32 / 7.0;
+            assert_eq!(x.round(), round_half_away(x), "{}", x);
+        }
+        for &x in &[0.5f32, 1.5, 2.5, -0.5, -1.5, 254.5, 0.49999997] {
+            assert_eq!(x.round(), round_half_away(x), "{}", x);
+        }
+    }
 }
@@ -1,174 +1,2847 @@
-use std::io;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use image::{png::PngEncoder, ColorType, ImageBuffer, ImageError, Rgb, RgbImage};
+extern crate alloc;
 
-use hsl::HSL;
-use nibbler::Nibbler;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
 
+#[cfg(feature = "std")]
+pub use image::{Rgb, Rgba};
+#[cfg(not(feature = "std"))]
+pub use rgb::{Rgb, Rgba};
+
+use blockies::Blockies;
+use contrast::contrast_ratio;
+pub use error::{ColorParseError, IdenticonError, ModeParseError, RenderError};
+use hsl::round;
+pub use hsl::HSL;
+pub use nibbler::Nibbler;
+#[cfg(feature = "ico")]
+pub use render::MAX_ICO_SIZES;
+#[cfg(feature = "std")]
+pub use render::{render_grid, sprite_sheet, Renderer};
+#[cfg(feature = "wasm")]
+pub use wasm::render_png;
+
+mod blockies;
+mod contrast;
+mod error;
 mod hsl;
 mod nibbler;
+#[cfg(feature = "std")]
+mod render;
+#[cfg(not(feature = "std"))]
+mod rgb;
+#[cfg(feature = "serde")]
+mod rgb_serde;
+#[cfg(feature = "wasm")]
+mod wasm;
 
+#[derive(Clone, Debug)]
 pub struct Identicon<'a> {
-    source: &'a [u8],
+    source: Cow<'a, [u8]>,
     size: u32,
+    // Set apart from `size` only for non-square images.
+    height: Option<u32>,
+    grid_size: u32,
+    generating_columns: Option<u32>,
+    margin: Option<Margin>,
+    corner_radius: u32,
+    cell_shape: CellShape,
+    symmetry: Symmetry,
+    density: Option<f32>,
+    fill_fn: Option<FillFn>,
+    // Cells to paint as they are, instead of generating them from the source.
+    mask: Option<Vec<bool>>,
     mode: Mode,
-    background: Rgb<u8>,
+    color_bytes: Option<Range<usize>>,
+    theme: Theme,
+    foreground: Option<Foreground>,
+    palette: Option<Vec<Rgb<u8>>>,
+    two_tone: bool,
+    invert: bool,
+    min_contrast: Option<f32>,
+    stroke: Option<(u32, Rgb<u8>)>,
+    frame: Option<(u32, Rgb<u8>)>,
+    samples: u8,
+    opacity: f32,
+    linear_blending: bool,
+    adjust: (f32, f32),
+    tint: Option<(Rgb<u8>, f32)>,
+    background: Option<Background>,
 }
 
 impl<'a> Identicon<'a> {
-    pub fn new(source: &[u8]) -> Identicon {
+    /// Create an identicon from a hash of the input, such as an MD5 digest.
+    ///
+    /// The default `Mode::GitHub` reads the first 16 bytes of `source`, and
+    /// rendering panics if there are fewer. Use `try_new` to check up front.
+    pub fn new(source: &[u8]) -> Identicon<'_> {
+        Identicon::with_source(Cow::Borrowed(source))
+    }
+
+    /// Like `new`, but taking ownership of `source`, so the identicon can be
+    /// stored or returned without tying it to a borrow.
+    pub fn owned(source: Vec<u8>) -> Identicon<'static> {
+        Identicon::with_source(Cow::Owned(source))
+    }
+
+    /// Copy a borrowed source, if needed, to detach the identicon and its
+    /// options from the borrow.
+    pub fn into_owned(self) -> Identicon<'static> {
+        Identicon {
+            source: Cow::Owned(self.source.into_owned()),
+            ..self
+        }
+    }
+
+    /// Create an identicon from arbitrary input, which is first hashed with
+    /// SHA-256 so there are always enough bytes for every mode.
+    #[cfg(feature = "sha2")]
+    pub fn from_bytes(input: &[u8]) -> Identicon<'static> {
+        use sha2::{Digest, Sha256};
+
+        let digest = Sha256::digest(input);
+        Identicon::with_source(Cow::Owned(digest.to_vec()))
+    }
+
+    /// Create an identicon for a string identity, such as a login or email.
+    #[cfg(feature = "sha2")]
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Identicon<'static> {
+        Identicon::from_bytes(s.as_bytes())
+    }
+
+    /// Create an identicon for a numeric ID. `seed` is stretched to 16 bytes,
+    /// two big-endian outputs of the SplitMix64 mixer, so that consecutive IDs
+    /// still look nothing alike.
+    pub fn from_u64(seed: u64) -> Identicon<'static> {
+        let mut state = seed;
+        let mut source = Vec::with_capacity(16);
+        for _ in 0..2 {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            source.extend_from_slice(&(z ^ (z >> 31)).to_be_bytes());
+        }
+        Identicon::with_source(Cow::Owned(source))
+    }
+
+    /// Recreate the default 5x5 pattern from a bitmask, such as one stored
+    /// compactly in a database: bit `i`, counting from the least significant,
+    /// paints cell `i` of `pixel_grid()`, which is row `i / 5` and column
+    /// `i % 5`. Bits past the 25th are ignored.
+    ///
+    /// The cells are taken as they are, so options that shape the pattern,
+    /// like `symmetry` or `density`, have no effect, and `grid_size` should
+    /// stay at 5.
+    pub fn from_mask(mask: u32, fg: Rgb<u8>, bg: Rgb<u8>, size: u32) -> Identicon<'static> {
+        let cells = (0..25).map(|i| mask >> i & 1 == 1).collect();
+        Identicon::with_mask(cells, 5)
+            .size(size)
+            .foreground(fg)
+            .background(bg)
+    }
+
+    // An identicon painting `cells` in a `grid_size` grid, without a source.
+    fn with_mask(cells: Vec<bool>, grid_size: u32) -> Identicon<'static> {
+        Identicon {
+            grid_size,
+            mask: Some(cells),
+            ..Identicon::with_source(Cow::Borrowed(&[]))
+        }
+    }
+
+    fn with_source(source: Cow<'a, [u8]>) -> Identicon<'a> {
         Identicon {
-            source: source,
+            source,
             size: 420,
+            height: None,
+            grid_size: 5,
+            generating_columns: None,
+            margin: None,
+            corner_radius: 0,
+            cell_shape: CellShape::Square,
+            symmetry: Symmetry::Horizontal,
+            density: None,
+            fill_fn: None,
+            mask: None,
             mode: Mode::GitHub,
-            background: Rgb([240, 240, 240]),
+            color_bytes: None,
+            theme: Theme::GitHubLight,
+            foreground: None,
+            palette: None,
+            two_tone: false,
+            invert: false,
+            min_contrast: None,
+            stroke: None,
+            frame: None,
+            samples: 1,
+            opacity: 1.0,
+            linear_blending: false,
+            adjust: (0.0, 1.0),
+            tint: None,
+            background: None,
+        }
+    }
+
+    /// Start a reusable set of options, to `build` identicons for many
+    /// sources with the same look.
+    pub fn builder() -> IdenticonConfig {
+        IdenticonConfig::default()
+    }
+
+    /// Like `new`, but returns an error if `source` is too short to render.
+    pub fn try_new(source: &[u8]) -> Result<Identicon<'_>, IdenticonError> {
+        let identicon = Identicon::new(source);
+        let needed = identicon.mode.min_source_len();
+        if source.len() < needed {
+            return Err(IdenticonError::SourceTooShort {
+                needed,
+                got: source.len(),
+            });
+        }
+        Ok(identicon)
+    }
+
+    /// Mix `salt` into the source, so that each application using its own
+    /// salt draws different icons for the same input. The salt's bytes are
+    /// XORed into the source in turn, wrapping around it as often as needed.
+    /// An empty salt changes nothing.
+    pub fn salt(mut self, salt: &[u8]) -> Self {
+        if salt.is_empty() || self.source.is_empty() {
+            return self;
         }
+        let source = self.source.to_mut();
+        let len = source.len();
+        for (ix, byte) in salt.iter().enumerate() {
+            source[ix % len] ^= byte;
+        }
+        self
+    }
+
+    /// Set the width and height of the rendered image, in pixels.
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self.height = None;
+        self
+    }
+
+    /// Render a `width` by `height` image instead of a square one, with the
+    /// cells and margins stretched to fill each axis on its own. Cells are
+    /// left out along an axis too short to fit one pixel each.
+    pub fn dimensions(mut self, width: u32, height: u32) -> Self {
+        self.size = width;
+        self.height = Some(height);
+        self
+    }
+
+    /// Set the size to a whole multiple of the smallest crisp layout, where
+    /// each cell is 2 pixels with a 1 pixel margin around the grid, so that
+    /// nothing is left over to round away: `scale(2)` gives 4 pixel cells. A
+    /// margin set in pixels is kept as it is, and a `margin_ratio` goes back
+    /// to the default half cell. Call it after `grid_size` and `margin`,
+    /// since it works from them. Sizes too big for a `u32` stop at `u32::MAX`.
+    pub fn scale(mut self, factor: u32) -> Self {
+        let margin = match self.margin {
+            Some(Margin::Pixels(margin)) => margin,
+            _ => {
+                self.margin = None;
+                factor
+            }
+        };
+        let size = factor
+            .saturating_mul(2)
+            .saturating_mul(self.grid_size)
+            .saturating_add(margin.saturating_mul(2));
+        self.size(size)
+    }
+
+    // The width and height of the rendered image.
+    fn bounds(&self) -> (u32, u32) {
+        (self.size, self.height.unwrap_or(self.size))
+    }
+
+    /// Set the number of cells along each side of the grid. Defaults to 5.
+    ///
+    /// The left `(n + 1) / 2` columns are generated from the source and
+    /// mirrored onto the right, sharing the center column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is even, since there would be no center column.
+    pub fn grid_size(mut self, n: u32) -> Self {
+        assert!(n % 2 == 1, "grid size must be odd, got {}", n);
+        self.grid_size = n;
+        self
+    }
+
+    /// Like `grid_size`, but returns an error rather than panicking if `n` is
+    /// even.
+    pub fn try_grid_size(self, n: u32) -> Result<Self, IdenticonError> {
+        if n.is_multiple_of(2) {
+            return Err(IdenticonError::InvalidGridSize(n));
+        }
+        Ok(self.grid_size(n))
+    }
+
+    /// Generate only the `n` columns in the middle of the grid from the
+    /// source, and fill the others by reflecting those outward, back and forth
+    /// like a concertina. Fewer columns make for simpler patterns. Applies to
+    /// `Symmetry::Horizontal` and `Symmetry::Quad`; the default is all
+    /// `(grid_size + 1) / 2` columns up to and including the center.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0 or more than `(grid_size + 1) / 2`.
+    pub fn generating_columns(mut self, n: u32) -> Self {
+        let half = self.grid_size.div_ceil(2);
+        assert!(
+            (1..=half).contains(&n),
+            "generating columns must be from 1 to {}, got {}",
+            half,
+            n
+        );
+        self.generating_columns = Some(n);
+        self
+    }
+
+    /// Set the empty border around the grid, in pixels.
+    ///
+    /// The cells shrink to fit so that `size = grid_size * pixel_size + 2 *
+    /// margin`, with any pixels left over from rounding split between the two
+    /// sides. By default the margin is half a cell, and a margin of 0 paints
+    /// flush to the edges. Replaces any `margin_ratio`.
+    pub fn margin(mut self, margin: u32) -> Self {
+        self.margin = Some(Margin::Pixels(margin));
+        self
+    }
+
+    /// Set the empty border around the grid as a share of the image's size,
+    /// such as 0.1 for 10% on each side, so it keeps its proportions at any
+    /// `size`. Clamped from 0.0 to 0.5. The default half-cell margin is
+    /// `0.5 / (grid_size + 1)` of the size, or 1/12 for a 5 by 5 grid, less
+    /// whatever the cells' rounding leaves over. Replaces any `margin`.
+    pub fn margin_ratio(mut self, ratio: f32) -> Self {
+        self.margin = Some(Margin::Ratio(ratio.clamp(0.0, 0.5)));
+        self
+    }
+
+    /// Round off the corners of each painted cell, in pixels. A radius over
+    /// half the cell size paints a circle. Defaults to 0, for square cells.
+    pub fn corner_radius(mut self, radius: u32) -> Self {
+        self.corner_radius = radius;
+        self
+    }
+
+    /// Choose the shape painted in each cell. Defaults to `CellShape::Square`.
+    pub fn cell_shape(mut self, shape: CellShape) -> Self {
+        self.cell_shape = shape;
+        self
+    }
+
+    /// Choose how the generated cells are mirrored across the grid.
+    /// Defaults to `Symmetry::Horizontal`.
+    pub fn symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// Choose the half of the grid that's generated from the source, with the
+    /// other mirrored from it: `Left` is the same as the default
+    /// `Symmetry::Horizontal`, and `Top` as `Symmetry::Vertical`.
+    pub fn mirror_axis(self, axis: MirrorAxis) -> Self {
+        self.symmetry(axis.into())
+    }
+
+    /// Set the share of cells that get painted, from 0.0 to 1.0.
+    ///
+    /// Each cell's nibble paints it when `nibble < density * 16`. By default a
+    /// cell is painted when its nibble is even, which also paints about half.
+    pub fn density(mut self, density: f32) -> Self {
+        self.density = Some(density.clamp(0.0, 1.0));
+        self.fill_fn = None;
+        self
+    }
+
+    /// Decide which cells get painted with your own test of each cell's
+    /// nibble, from 0 to 15, in place of `density` or the default
+    /// `|x| x % 2 == 0`. Whichever of the two was set last applies.
+    pub fn fill_fn(mut self, f: impl Fn(u8) -> bool + Send + Sync + 'static) -> Self {
+        self.fill_fn = Some(FillFn(Arc::new(f)));
+        self.density = None;
+        self
     }
 
+    /// Choose how the colors, and for `Mode::Blockies` the pattern too, are
+    /// derived from the source. `Mode::Blockies` also switches to its 8x8
+    /// grid with no margin, which can be changed again afterwards.
     pub fn mode(mut self, mode: Mode) -> Self {
+        if mode == Mode::Blockies {
+            self.grid_size = 8;
+            self.margin = Some(Margin::Pixels(0));
+        }
         self.mode = mode;
         self
     }
 
+    /// Paint cells in a fixed color instead of one derived from the source.
+    pub fn foreground(mut self, color: Rgb<u8>) -> Self {
+        self.foreground = Some(Foreground::Solid(color));
+        self
+    }
+
+    /// Paint cells with a fixed solid color or gradient instead of one
+    /// derived from the source.
+    pub fn foreground_fill(mut self, fill: Foreground) -> Self {
+        self.foreground = Some(fill);
+        self
+    }
+
+    /// Paint cells in one of `colors`, such as a brand palette, picked by the
+    /// source instead of computing a color, so the same source always gets
+    /// the same entry. The source's bytes, or the `color_bytes` range, are
+    /// XORed together into a 32-bit index modulo the palette's length. A fixed
+    /// `foreground` takes precedence, and the palette takes precedence over
+    /// `two_tone`, the mode's colors, and `ensure_contrast`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `colors` is empty.
+    pub fn palette(mut self, colors: Vec<Rgb<u8>>) -> Self {
+        assert!(!colors.is_empty(), "palette must have at least one color");
+        self.palette = Some(colors);
+        self
+    }
+
+    /// Like `palette`, but returns an error rather than panicking if `colors`
+    /// is empty.
+    pub fn try_palette(self, colors: Vec<Rgb<u8>>) -> Result<Self, IdenticonError> {
+        if colors.is_empty() {
+            return Err(IdenticonError::EmptyPalette);
+        }
+        Ok(self.palette(colors))
+    }
+
+    /// Paint each cell in one of two hash-derived colors: the usual one for
+    /// the mode, and a second with its hue rotated by 30 to 180 degrees.
+    /// Ignored when a fixed foreground is set.
+    pub fn two_tone(mut self, enabled: bool) -> Self {
+        self.two_tone = enabled;
+        self
+    }
+
+    /// Swap which cells are painted, for the negative of the usual pattern,
+    /// such as to show a selected state. The colors stay the same, and
+    /// inverting again swaps the cells back.
+    pub fn invert(mut self) -> Self {
+        self.invert = !self.invert;
+        self
+    }
+
+    /// Adjust the luminance of colors derived from the source, if needed, so
+    /// that they have a WCAG contrast ratio of at least `min_ratio` against
+    /// the background: 4.5 is enough for AA text, for example. Ratios run from
+    /// 1.0 to 21.0, and if `min_ratio` can't be reached the color goes as far
+    /// as it can. Off by default. Fixed foregrounds are left alone.
+    pub fn ensure_contrast(mut self, min_ratio: f32) -> Self {
+        self.min_contrast = Some(min_ratio.clamp(1.0, 21.0));
+        self
+    }
+
+    /// Outline each painted cell in raster output with a border `width` pixels
+    /// wide, centered on the cell's edge so that neighboring cells share a
+    /// single line. A width of 0 turns the outline off, which is the default.
+    pub fn stroke(mut self, width: u32, color: Rgb<u8>) -> Self {
+        self.stroke = if width == 0 {
+            None
+        } else {
+            Some((width, color))
+        };
+        self
+    }
+
+    /// Paint a border `width` pixels wide around the edges of raster output,
+    /// over the margin and anything drawn there. A width of 0 turns the frame
+    /// off, which is the default.
+    pub fn frame(mut self, width: u32, color: Rgb<u8>) -> Self {
+        self.frame = if width == 0 {
+            None
+        } else {
+            Some((width, color))
+        };
+        self
+    }
+
+    /// Smooth the edges of circles, hexagons, and rounded corners by rendering
+    /// at `samples` times the resolution and averaging back down. Capped at
+    /// 4; the default of 1 turns it off. Square cells come out unchanged.
+    pub fn antialias(mut self, samples: u8) -> Self {
+        self.samples = samples.clamp(1, 4);
+        self
+    }
+
+    /// Paint the cells partly see-through, blended over the background, from
+    /// 0.0 for invisible to the default 1.0 for solid. Strokes stay solid.
+    pub fn opacity(mut self, alpha: f32) -> Self {
+        self.opacity = alpha.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Mix colors in linear light rather than on their sRGB values, where
+    /// raster output blends them: for `opacity`, the averaging of `antialias`,
+    /// and `tint`. Antialiased edges then keep their brightness instead of
+    /// darkening, at some cost in speed. Off by default.
+    pub fn linear_blending(mut self, enabled: bool) -> Self {
+        self.linear_blending = enabled;
+        self
+    }
+
+    /// Adjust the brightness and contrast of raster output as a last step,
+    /// background included. Each channel value `v`, from 0.0 to 1.0, becomes
+    /// `(v - 0.5) * contrast + 0.5 + brightness`. The default of `(0.0, 1.0)`
+    /// changes nothing; a brightness of -0.1 darkens slightly, and a contrast
+    /// below 1.0 pulls colors toward gray. A NaN brightness or contrast
+    /// leaves that part unchanged.
+    pub fn adjust(mut self, brightness: f32, contrast: f32) -> Self {
+        let defined = |v: f32, neutral| if v.is_nan() { neutral } else { v };
+        self.adjust = (defined(brightness, 0.0), defined(contrast, 1.0).max(0.0));
+        self
+    }
+
+    /// Blend all of raster output toward `color` by `strength`, from 0.0 for
+    /// no change to 1.0 for flat `color`, such as a sepia tone for a vintage
+    /// look. This comes after `adjust`, and before `frame`, which keeps its
+    /// own color. A NaN strength changes nothing.
+    pub fn tint(mut self, color: Rgb<u8>, strength: f32) -> Self {
+        let strength = strength.clamp(0.0, 1.0);
+        self.tint = if strength == 0.0 || strength.is_nan() {
+            None
+        } else {
+            Some((color, strength))
+        };
+        self
+    }
+
+    /// Apply a preset look: a background color, plus the saturation and
+    /// luminance ranges that `Mode::GitHub` picks colors from. The default is
+    /// `Theme::GitHubLight`.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self.background = Some(Background::Solid(theme.background()));
+        self
+    }
+
+    /// Set the color of the canvas behind the painted cells.
+    /// Defaults to a light gray, `Rgb([240, 240, 240])`.
+    pub fn background(mut self, color: Rgb<u8>) -> Self {
+        self.background = Some(Background::Solid(color));
+        self
+    }
+
     pub fn background_rgb(mut self, r: u8, g: u8, b: u8) -> Self {
-        self.background = Rgb([r,g,b]);
+        self.background = Some(Background::Solid(Rgb([r, g, b])));
+        self
+    }
+
+    /// Fill the canvas behind the painted cells with a solid color, a
+    /// checkerboard pattern, or a gradient.
+    pub fn background_fill(mut self, fill: Background) -> Self {
+        self.background = Some(fill);
+        self
+    }
+
+    /// Take the color from `range` of the source rather than the bytes the
+    /// mode normally reads, so that color and pattern don't overlap. Longer
+    /// ranges are XORed down to the four bytes a color needs, so every byte
+    /// counts.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is shorter than 4 bytes or reaches past the source.
+    pub fn color_bytes(mut self, range: Range<usize>) -> Self {
+        assert!(
+            range.len() >= 4 && range.end <= self.source.len(),
+            "color bytes {:?} must span at least 4 of the {} source bytes",
+            range,
+            self.source.len()
+        );
+        self.color_bytes = Some(range);
         self
     }
 
-    fn foreground(&self) -> Rgb<u8> {
+    // The color derived from the source by the selected mode.
+    fn mode_color(&self) -> HSL {
+        let source = match &self.color_bytes {
+            Some(range) => &self.source[range.clone()],
+            None => &self.source[..],
+        };
+        self.mode.color(source, self.color_key(), self.theme)
+    }
+
+    /// The color the cells are painted in, for styling things around the
+    /// icon to match. This is the fixed foreground when one is set, or the
+    /// start of a gradient, and otherwise the color from the mode after
+    /// `ensure_contrast`. With `two_tone` or several colors, it's the first.
+    pub fn foreground_color(&self) -> Rgb<u8> {
+        match self.foreground {
+            Some(Foreground::Solid(color)) => color,
+            Some(Foreground::Gradient { start, .. }) => start,
+            None => match self.palette_color() {
+                Some(color) => color,
+                None => self.contrasted(self.mode_color()),
+            },
+        }
+    }
+
+    /// Both colors as `(foreground, background)`, for styling things around
+    /// the icon to match: the foreground is `foreground_color`, and a checkered
+    /// or gradient background gives its first color. `contrast_ratio()`
+    /// tells how well the icon's colors read together.
+    pub fn colors(&self) -> (Rgb<u8>, Rgb<u8>) {
+        let background = match self.backdrop() {
+            Background::Solid(color) => color,
+            Background::Checker { a, .. } => a,
+            Background::LinearGradient { start, .. } => start,
+        };
+        (self.foreground_color(), background)
+    }
+
+    // The four bytes the mode's color is computed from.
+    fn color_key(&self) -> [u8; 4] {
+        match &self.color_bytes {
+            Some(range) => fold_key(&self.source[range.clone()]),
+            None => self.mode.color_key(&self.source),
+        }
+    }
+
+    // The entry of `palette` this source picks, if there's a palette.
+    fn palette_color(&self) -> Option<Rgb<u8>> {
+        let colors = self.palette.as_ref()?;
+        let source = match &self.color_bytes {
+            Some(range) => &self.source[range.clone()],
+            None => &self.source[..],
+        };
+        let index = u32::from_be_bytes(fold_key(source)) as usize;
+        Some(colors[index % colors.len()])
+    }
+
+    /// Returns which cells are painted, for drawing the icon yourself.
+    ///
+    /// The grid is `grid_size` cells wide and tall, in row-major order: cell
+    /// `(row, col)` is at index `row * grid_size + col`. This is the same layout
+    /// `image()` and `svg()` draw.
+    pub fn pixel_grid(&self) -> Vec<bool> {
+        self.pixels()
+    }
+
+    /// Packs `pixel_grid()` into a bitmask in the order `from_mask` reads,
+    /// with bit `i` set when cell `i` is painted: a compact fingerprint of
+    /// the pattern, for storing or for pinning in tests.
+    ///
+    /// # Panics
+    ///
+    /// If the grid has more than the 32 cells that fit, past a `grid_size`
+    /// of 5.
+    pub fn pixel_mask(&self) -> u32 {
+        assert!(
+            self.grid_size <= 5,
+            "a {0}x{0} grid doesn't fit in a 32-bit mask",
+            self.grid_size
+        );
+        self.cells()
+            .enumerate()
+            .fold(0, |mask, (ix, (_, _, painted))| {
+                mask | (painted as u32) << ix
+            })
+    }
+
+    /// Iterates over every cell as `(row, col, painted)`, in row-major order.
+    pub fn cells(&self) -> impl Iterator<Item = (u32, u32, bool)> + '_ {
+        let n = self.grid_size;
+        let blockies = self.blockies();
+        (0..n * n).map(move |ix| {
+            let (row, col) = (ix / n, ix % n);
+            let painted = match (&self.mask, &blockies) {
+                (Some(mask), _) => mask.get(ix as usize) == Some(&true),
+                (None, Some(blockies)) => blockies.cells[ix as usize] != 0,
+                (None, None) => self.painted(row as usize, col as usize),
+            };
+            (row, col, painted != self.invert)
+        })
+    }
+
+    // The palette and pattern for `Mode::Blockies`, which doesn't use nibbles.
+    fn blockies(&self) -> Option<Blockies> {
         match self.mode {
-            Mode::GitHub => {
-                // Use last 28 bits to determine HSL values.
-                let h1 = (self.source[12] as u16 & 0x0f) << 8;
-                let h2 = self.source[13] as u16;
+            Mode::Blockies => Some(Blockies::new(&self.source, self.grid_size as usize)),
+            _ => None,
+        }
+    }
+
+    // The fill behind the cells: the one that was set, or else the mode's.
+    fn backdrop(&self) -> Background {
+        match (self.background, self.blockies()) {
+            (Some(fill), _) => fill,
+            (None, Some(blockies)) => Background::Solid(blockies.background.rgb()),
+            (None, None) => Background::default(),
+        }
+    }
+
+    fn pixels(&self) -> Vec<bool> {
+        self.pixel_iter().collect()
+    }
+
+    // Whether each cell is painted, in row-major order, without collecting
+    // them first.
+    fn pixel_iter(&self) -> impl Iterator<Item = bool> + '_ {
+        self.cells().map(|(_, _, painted)| painted)
+    }
 
-                let h = (h1 | h2) as u32;
-                let s = self.source[14] as u32;
-                let l = self.source[15] as u32;
+    fn painted(&self, row: usize, col: usize) -> bool {
+        self.nibble(self.generator(row, col))
+            .is_some_and(|x| self.paints(x))
+    }
 
-                let hue = map(h, 0, 4095, 0, 360);
-                let sat = map(s, 0, 255, 0, 20);
-                let lum = map(l, 0, 255, 0, 20);
+    fn nibble(&self, ix: usize) -> Option<u8> {
+        Nibbler::new(&self.source).nth(ix)
+    }
 
-                HSL::new(hue, 65.0 - sat, 75.0 - lum).rgb()
+    // The index of the nibble that decides whether a cell is painted, in the
+    // order cells are generated. Mirrored cells share the nibble of the cell
+    // they copy.
+    fn generator(&self, row: usize, col: usize) -> usize {
+        let n = self.grid_size as usize;
+        let half = n.div_ceil(2);
+        let (mirror_row, mirror_col) = (row.min(n - 1 - row), col.min(n - 1 - col));
+        // Columns past the generated ones fold back onto them.
+        let mirror_col = match self.generating_columns {
+            Some(k) => {
+                let k = (k as usize).min(half);
+                let distance = (half - 1 - mirror_col) % (2 * k);
+                half - 1 - distance.min(2 * k - 1 - distance)
             }
-            Mode::IdenticonJS(IdenticonJSOptions {
-                saturation,
-                brightness,
-            }) => {
-                // Use last 28 bits to determine the hue.
-                // Note: Identicon.js uses the last bytes no matter how long the hash is:
-                let l = self.source.len();
-                let mut h: u32 = self.source[l - 4] as u32 & 0x0f;
-                h = h << 8 | (self.source[l - 3] as u32);
-                h = h << 8 | (self.source[l - 2] as u32);
-                h = h << 8 | (self.source[l - 1] as u32);
+            None => mirror_col,
+        };
+        match self.symmetry {
+            // Columns from the center outward, each top to bottom.
+            Symmetry::Horizontal => (half - 1 - mirror_col) * n + row,
+            // Rows from the center outward, each left to right.
+            Symmetry::Vertical => (half - 1 - mirror_row) * n + col,
+            // The top-left quadrant, including the shared center row and
+            // column, generated like `Horizontal`.
+            Symmetry::Quad => (half - 1 - mirror_col) * half + mirror_row,
+            // The upper triangle including the diagonal, row by row.
+            Symmetry::Diagonal => {
+                let (row, col) = (row.min(col), row.max(col));
+                row * n - row * row.saturating_sub(1) / 2 + (col - row)
+            }
+            Symmetry::None => row * n + col,
+        }
+    }
+
+    // Whether a cell generated from this nibble gets painted.
+    fn paints(&self, nibble: u8) -> bool {
+        match (&self.fill_fn, self.density) {
+            (Some(FillFn(f)), _) => f(nibble),
+            (None, Some(density)) => (nibble as f32) < density * 16.0,
+            (None, None) => nibble.is_multiple_of(2),
+        }
+    }
+
+    /// Returns the cell size and the offset of the first cell, in pixels, as
+    /// `(x, y)` pairs.
+    fn layout(&self) -> ((usize, usize), (usize, usize)) {
+        let (width, height) = self.bounds();
+        let (cell_width, x) = self.axis_layout(width);
+        let (cell_height, y) = self.axis_layout(height);
+        ((cell_width, cell_height), (x, y))
+    }
+
+    // The cell size and the offset of the first cell along an axis `size`
+    // pixels long.
+    fn axis_layout(&self, size: u32) -> (usize, usize) {
+        let sprite_size = self.grid_size as usize;
+        let margin = self.margin.map(|margin| match margin {
+            Margin::Pixels(margin) => margin,
+            Margin::Ratio(ratio) => round(ratio * size as f32) as u32,
+        });
+        let (pixel_size, margin) = match margin {
+            Some(margin) => {
+                let margin = margin.min(size / 2) as usize;
+                ((size as usize - 2 * margin) / sprite_size, margin)
+            }
+            None => {
+                // Leave room for half a cell on each side. At the default size of
+                // 420px this gives 70px cells with a 35px margin.
+                let pixel_size = size as usize / (sprite_size + 1);
+                (pixel_size, pixel_size / 2)
+            }
+        };
+
+        // Integer division can leave a few pixels unused. Split them between
+        // both sides so the sprite stays centered.
+        let leftover = size as usize - (pixel_size * sprite_size + 2 * margin);
+        (pixel_size, margin + leftover / 2)
+    }
+
+    // The color of every cell, in row-major order.
+    fn cell_colors(&self) -> Vec<Rgb<u8>> {
+        self.cell_paints().into_iter().map(Paint::rgb).collect()
+    }
+
+    // The color of every cell as it was worked out, so raster output wider
+    // than 8 bits can convert the derived ones itself.
+    fn cell_paints(&self) -> Vec<Paint> {
+        let n = self.grid_size as usize;
+        match self.foreground {
+            Some(Foreground::Solid(color)) => vec![Paint::Fixed(color); n * n],
+            Some(Foreground::Gradient { start, end }) => {
+                let rows = n.saturating_sub(1).max(1) as f32;
+                (0..n * n)
+                    .map(|ix| Paint::Mix(start, end, (ix / n) as f32 / rows))
+                    .collect()
+            }
+            None if self.palette.is_some() => vec![Paint::Fixed(self.foreground_color()); n * n],
+            None if self.mode == Mode::Blockies => {
+                let blockies = Blockies::new(&self.source, n);
+                let colors = [
+                    Paint::Derived(self.contrasted_hsl(blockies.color)),
+                    Paint::Derived(self.contrasted_hsl(blockies.spot)),
+                ];
+                blockies
+                    .cells
+                    .iter()
+                    .map(|&cell| colors[(cell == 2) as usize])
+                    .collect()
+            }
+            None if self.two_tone => self.assign_colors(|pick| {
+                // The first spare nibble picks the second hue.
+                let primary = self.mode_color();
+                let offset = 30.0 + map(pick as u32, 0, 15, 0, 150);
+                vec![
+                    Paint::Derived(self.contrasted_hsl(primary)),
+                    Paint::Derived(self.contrasted_hsl(primary.rotate(offset))),
+                ]
+            }),
+            None => match &self.mode {
+                Mode::IdenticonJS(options) if options.colors > 1 => self.assign_colors(|_| {
+                    let primary = self.mode_color();
+                    let count = options.colors.min(4);
+                    (0..count)
+                        .map(|k| primary.rotate(360.0 * k as f32 / count as f32))
+                        .map(|color| Paint::Derived(self.contrasted_hsl(color)))
+                        .collect()
+                }),
+                _ => vec![Paint::Derived(self.contrasted_hsl(self.mode_color())); n * n],
+            },
+        }
+    }
+
+    // Gives each generated cell one of the colors `palette` returns, with
+    // mirrored cells matching the ones they copy. The nibbles after the ones
+    // the pattern used do the picking: the first goes to `palette`, and then
+    // cell `g` in generation order takes the color at the next nibble `g + 1`,
+    // modulo how many colors there are. They wrap around a short source.
+    fn assign_colors<T: Copy>(&self, palette: impl FnOnce(u8) -> Vec<T>) -> Vec<T> {
+        let n = self.grid_size as usize;
+        let generators: Vec<usize> = (0..n * n)
+            .map(|ix| self.generator(ix / n, ix % n))
+            .collect();
+        let used = generators.iter().max().map_or(0, |g| g + 1);
+        let len = self.source.len() * 2;
+        let extra = |i: usize| match len {
+            0 => 0,
+            len => self.nibble((used + i) % len).unwrap_or(0),
+        };
+
+        let colors = palette(extra(0));
+        generators
+            .iter()
+            .map(|g| colors[extra(g + 1) as usize % colors.len()])
+            .collect()
+    }
+
+    /// The WCAG contrast ratio between the painted cells and the background,
+    /// from 1.0 to 21.0. When the icon has more than one color on either side,
+    /// such as with `two_tone`, this is the lowest ratio of any pair.
+    pub fn contrast_ratio(&self) -> f32 {
+        self.cell_colors()
+            .into_iter()
+            .map(|color| self.background_contrast(color))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    // The lowest contrast ratio between `color` and the background colors.
+    fn background_contrast(&self, color: Rgb<u8>) -> f32 {
+        let backgrounds = match self.backdrop() {
+            Background::Solid(color) => [color, color],
+            Background::Checker { a, b, .. } => [a, b],
+            Background::LinearGradient { start, end, .. } => [start, end],
+        };
+        backgrounds
+            .iter()
+            .map(|&bg| contrast_ratio(color, bg))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    // `color` with its luminance moved the least distance that meets
+    // `ensure_contrast` against every background color, or as close as it
+    // gets if nothing does.
+    fn contrasted(&self, color: HSL) -> Rgb<u8> {
+        self.contrasted_hsl(color).rgb()
+    }
 
-                let hue = map(h, 0, 0x0f_ff_ff_ff, 0, 360);
-                let sat = saturation * 100.0;
-                let lum = brightness * 100.0;
+    // `contrasted`, before it's rounded to 8-bit RGB.
+    fn contrasted_hsl(&self, color: HSL) -> HSL {
+        let min_ratio = match self.min_contrast {
+            Some(ratio) => ratio,
+            None => return color,
+        };
+        let ratio = |lum: f32| {
+            let rgb = HSL { lum, ..color }.rgb();
+            (lum, self.background_contrast(rgb))
+        };
 
-                HSL::new(hue, sat, lum).rgb()
+        let (mut best, mut best_ratio) = ratio(color.lum);
+        for step in 1..=100 {
+            if best_ratio >= min_ratio {
+                break;
+            }
+            for lum in [color.lum - step as f32, color.lum + step as f32] {
+                let (lum, worst) = ratio(lum.clamp(0.0, 100.0));
+                if worst > best_ratio {
+                    best = lum;
+                    best_ratio = worst;
+                }
             }
         }
+        HSL { lum: best, ..color }
     }
 
-    fn rect(image: &mut RgbImage, x0: u32, y0: u32, x1: u32, y1: u32, color: Rgb<u8>) {
-        for x in x0..x1 {
-            for y in y0..y1 {
-                image.put_pixel(x, y, color);
+    /// Render as text for terminals and logs: `██` for each painted cell and
+    /// two spaces for each empty one, with a newline after every row.
+    pub fn to_ascii(&self) -> String {
+        let mut ascii = String::new();
+        for (_, col, painted) in self.cells() {
+            ascii.push_str(if painted { "██" } else { "  " });
+            if col + 1 == self.grid_size {
+                ascii.push('\n');
             }
         }
+        ascii
     }
 
-    fn pixels(&self) -> [bool; 25] {
-        let mut nibbles = Nibbler::new(self.source).map(|x| x % 2 == 0);
-        let mut pixels = [false; 25];
-        for col in (0..3).rev() {
-            for row in 0..5 {
-                let ix = col + (row * 5);
-                let mirror_col = 4 - col;
-                let mirror_ix = mirror_col + (row * 5);
-                let paint = nibbles.next().unwrap_or(false);
-                pixels[ix] = paint;
-                pixels[mirror_ix] = paint;
+    /// Render in true color for terminals, as pairs of spaces with 24-bit ANSI
+    /// background colors. Every row ends by resetting the terminal's colors.
+    /// A checkered or gradient background shows as its first color.
+    pub fn to_ansi(&self) -> String {
+        let (_, background) = self.colors();
+        let colors = self.cell_colors();
+
+        let mut ansi = String::new();
+        for (ix, (_, col, painted)) in self.cells().enumerate() {
+            let Rgb([r, g, b]) = if painted { colors[ix] } else { background };
+            ansi.push_str(&format!("\x1b[48;2;{};{};{}m  ", r, g, b));
+            if col + 1 == self.grid_size {
+                ansi.push_str("\x1b[0m\n");
             }
         }
-        pixels
+        ansi
     }
 
-    pub fn image(&self) -> RgbImage {
-        let pixel_size = 70;
-        let sprite_size = 5;
-        let margin = pixel_size / 2;
+    /// Render as a standalone SVG document, with one `<rect>` per painted cell.
+    pub fn svg(&self) -> String {
+        let sprite_size = self.grid_size as usize;
+        let ((cell_width, cell_height), (x, y)) = self.layout();
+        let (width, height) = self.bounds();
 
-        let foreground = self.foreground();
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            width, height,
+        );
 
-        let mut image: RgbImage = ImageBuffer::from_pixel(self.size, self.size, self.background);
+        let fill = match self.backdrop() {
+            Background::Solid(color) => hex(color),
+            Background::Checker { a, b, cell } => {
+                let cell = cell.max(1);
+                svg.push_str(&format!(
+                    concat!(
+                        r#"<defs><pattern id="checker" width="{0}" height="{0}" patternUnits="userSpaceOnUse">"#,
+                        r#"<rect width="{0}" height="{0}" fill="{1}"/>"#,
+                        r#"<rect x="{2}" width="{2}" height="{2}" fill="{3}"/>"#,
+                        r#"<rect y="{2}" width="{2}" height="{2}" fill="{3}"/>"#,
+                        "</pattern></defs>",
+                    ),
+                    2 * cell,
+                    hex(a),
+                    cell,
+                    hex(b),
+                ));
+                String::from("url(#checker)")
+            }
+            Background::LinearGradient {
+                start,
+                end,
+                vertical,
+            } => {
+                svg.push_str(&format!(
+                    concat!(
+                        r#"<defs><linearGradient id="gradient" x2="{0}" y2="1">"#,
+                        r#"<stop offset="0" stop-color="{1}"/>"#,
+                        r#"<stop offset="1" stop-color="{2}"/>"#,
+                        "</linearGradient></defs>",
+                    ),
+                    if vertical { 0 } else { 1 },
+                    hex(start),
+                    hex(end),
+                ));
+                String::from("url(#gradient)")
+            }
+        };
+        svg.push_str(&format!(
+            r#"<rect width="{}" height="{}" fill="{}"/>"#,
+            width, height, fill,
+        ));
 
+        let colors = self.cell_colors();
+        let opacity = match self.opacity {
+            opacity if opacity >= 1.0 => String::new(),
+            opacity => format!(r#" fill-opacity="{}""#, opacity),
+        };
         for (row, pix) in self.pixels().chunks(sprite_size).enumerate() {
             for (col, painted) in pix.iter().enumerate() {
                 if *painted {
-                    let x = col * pixel_size;
-                    let y = row * pixel_size;
-                    Identicon::rect(
-                        &mut image,
-                        (x + margin) as u32,
-                        (y + margin) as u32,
-                        (x + pixel_size + margin) as u32,
-                        (y + pixel_size + margin) as u32,
+                    let foreground = hex(colors[col + row * sprite_size]);
+                    svg.push_str(&format!(
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"{}/>"#,
+                        col * cell_width + x,
+                        row * cell_height + y,
+                        cell_width,
+                        cell_height,
                         foreground,
-                    );
+                        opacity,
+                    ));
                 }
             }
         }
 
-        image
-    }
-
-    /// Write out a PNG file.
-    pub fn to_png<W: io::Write>(&self, writer: &mut W) -> Result<(), ImageError> {
-        let img = self.image();
-        let encoder = PngEncoder::new(writer);
-        let (w, h) = img.dimensions();
-        encoder.encode(img.as_ref(), w, h, ColorType::Rgb8)
+        svg.push_str("</svg>");
+        svg
     }
 }
 
-/// Which compatibility mode should we generate an Identicon with.
-pub enum Mode {
-    /// Generate GitHub-compatible emoticons. This is the default.
-    GitHub,
-
-    /// Identicon.js calculates colors differently.
-    IdenticonJS(IdenticonJSOptions),
+/// Borrow anything byte-like as the source, such as a `Vec<u8>`, a `String`
+/// or a `[u8; 32]` digest, the same as `Identicon::new`.
+impl<'a, T: AsRef<[u8]> + ?Sized> From<&'a T> for Identicon<'a> {
+    fn from(source: &'a T) -> Self {
+        Identicon::new(source.as_ref())
+    }
 }
 
-/// Identicon.js uses a constant saturation/brightness for generating icons.
-/// values between 0.0 and 1.0, inclusive.
-pub struct IdenticonJSOptions {
-    pub saturation: f32,
-    pub brightness: f32,
+/// Options for building identicons, kept apart from any source so that one
+/// config can be cloned, tweaked, and applied to many sources. Each setter
+/// works like the `Identicon` method of the same name.
+#[derive(Clone, Debug)]
+pub struct IdenticonConfig {
+    base: Identicon<'static>,
+    salt: Vec<u8>,
 }
 
-impl Default for IdenticonJSOptions {
+impl Default for IdenticonConfig {
     fn default() -> Self {
-        // See: https://github.com/stewartlord/identicon.js/blob/4fad7cafec1b7a4d896015e084e861625ef5d64f/identicon.js#L30-L31
-        IdenticonJSOptions {
-            saturation: 0.7,
-            brightness: 0.5,
+        IdenticonConfig {
+            base: Identicon::new(&[]),
+            salt: Vec::new(),
         }
     }
 }
 
-// https://processing.org/reference/map_.html
-fn map(value: u32, vmin: u32, vmax: u32, dmin: u32, dmax: u32) -> f32 {
-    (value - vmin) as f32 * ((dmax - dmin) as f32 / (vmax - vmin) as f32) + (dmin as f32)
-}
+impl IdenticonConfig {
+    /// Create an identicon for `source` with these options.
+    ///
+    /// # Panics
+    ///
+    /// If `color_bytes` reaches past the end of `source`.
+    pub fn build<'a>(&self, source: &'a [u8]) -> Identicon<'a> {
+        let identicon = Identicon {
+            source: Cow::Borrowed(source),
+            ..self.base.clone()
+        }
+        .salt(&self.salt);
+        match identicon.color_bytes.clone() {
+            Some(range) => identicon.color_bytes(range),
+            None => identicon,
+        }
+    }
+
+    pub fn salt(mut self, salt: &[u8]) -> Self {
+        self.salt = salt.to_vec();
+        self
+    }
+
+    pub fn size(mut self, size: u32) -> Self {
+        self.base = self.base.size(size);
+        self
+    }
+
+    pub fn dimensions(mut self, width: u32, height: u32) -> Self {
+        self.base = self.base.dimensions(width, height);
+        self
+    }
+
+    pub fn scale(mut self, factor: u32) -> Self {
+        self.base = self.base.scale(factor);
+        self
+    }
+
+    pub fn grid_size(mut self, n: u32) -> Self {
+        self.base = self.base.grid_size(n);
+        self
+    }
+
+    pub fn generating_columns(mut self, n: u32) -> Self {
+        self.base = self.base.generating_columns(n);
+        self
+    }
+
+    pub fn margin(mut self, margin: u32) -> Self {
+        self.base = self.base.margin(margin);
+        self
+    }
+
+    pub fn margin_ratio(mut self, ratio: f32) -> Self {
+        self.base = self.base.margin_ratio(ratio);
+        self
+    }
+
+    pub fn corner_radius(mut self, radius: u32) -> Self {
+        self.base = self.base.corner_radius(radius);
+        self
+    }
+
+    pub fn cell_shape(mut self, shape: CellShape) -> Self {
+        self.base = self.base.cell_shape(shape);
+        self
+    }
 
-#[test]
-fn test_map() {
-    assert_eq!(20.0, map(0, 0, 100, 20, 120));
+    pub fn symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.base = self.base.symmetry(symmetry);
+        self
+    }
 
-    assert_eq!(120.0, map(100, 0, 100, 20, 120));
+    pub fn mirror_axis(mut self, axis: MirrorAxis) -> Self {
+        self.base = self.base.mirror_axis(axis);
+        self
+    }
+
+    pub fn density(mut self, density: f32) -> Self {
+        self.base = self.base.density(density);
+        self
+    }
+
+    pub fn fill_fn(mut self, f: impl Fn(u8) -> bool + Send + Sync + 'static) -> Self {
+        self.base = self.base.fill_fn(f);
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.base = self.base.mode(mode);
+        self
+    }
+
+    pub fn foreground(mut self, color: Rgb<u8>) -> Self {
+        self.base = self.base.foreground(color);
+        self
+    }
+
+    pub fn palette(mut self, colors: Vec<Rgb<u8>>) -> Self {
+        self.base = self.base.palette(colors);
+        self
+    }
+
+    pub fn foreground_fill(mut self, fill: Foreground) -> Self {
+        self.base = self.base.foreground_fill(fill);
+        self
+    }
+
+    pub fn two_tone(mut self, enabled: bool) -> Self {
+        self.base = self.base.two_tone(enabled);
+        self
+    }
+
+    pub fn invert(mut self) -> Self {
+        self.base = self.base.invert();
+        self
+    }
+
+    pub fn ensure_contrast(mut self, min_ratio: f32) -> Self {
+        self.base = self.base.ensure_contrast(min_ratio);
+        self
+    }
+
+    pub fn stroke(mut self, width: u32, color: Rgb<u8>) -> Self {
+        self.base = self.base.stroke(width, color);
+        self
+    }
+
+    pub fn frame(mut self, width: u32, color: Rgb<u8>) -> Self {
+        self.base = self.base.frame(width, color);
+        self
+    }
+
+    pub fn antialias(mut self, samples: u8) -> Self {
+        self.base = self.base.antialias(samples);
+        self
+    }
+
+    pub fn opacity(mut self, alpha: f32) -> Self {
+        self.base = self.base.opacity(alpha);
+        self
+    }
+
+    pub fn linear_blending(mut self, enabled: bool) -> Self {
+        self.base = self.base.linear_blending(enabled);
+        self
+    }
+
+    pub fn adjust(mut self, brightness: f32, contrast: f32) -> Self {
+        self.base = self.base.adjust(brightness, contrast);
+        self
+    }
+
+    pub fn tint(mut self, color: Rgb<u8>, strength: f32) -> Self {
+        self.base = self.base.tint(color, strength);
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.base = self.base.theme(theme);
+        self
+    }
+
+    pub fn background(mut self, color: Rgb<u8>) -> Self {
+        self.base = self.base.background(color);
+        self
+    }
+
+    pub fn background_rgb(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.base = self.base.background_rgb(r, g, b);
+        self
+    }
+
+    pub fn background_fill(mut self, fill: Background) -> Self {
+        self.base = self.base.background_fill(fill);
+        self
+    }
+
+    /// There's no source to check `range` against yet, so that waits for
+    /// `build`.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is shorter than 4 bytes.
+    pub fn color_bytes(mut self, range: Range<usize>) -> Self {
+        assert!(
+            range.len() >= 4,
+            "color bytes {:?} must span at least 4 bytes",
+            range
+        );
+        self.base.color_bytes = Some(range);
+        self
+    }
+}
+
+// The border from `margin` or `margin_ratio`, whichever was set last.
+#[derive(Clone, Copy, Debug)]
+enum Margin {
+    Pixels(u32),
+    Ratio(f32),
+}
+
+// The predicate from `fill_fn`, shared between clones.
+#[derive(Clone)]
+struct FillFn(Arc<dyn Fn(u8) -> bool + Send + Sync>);
+
+impl fmt::Debug for FillFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FillFn")
+    }
+}
+
+/// A fixed fill for the painted cells, overriding the color from `Mode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Foreground {
+    /// Every cell in one color.
+    Solid(#[cfg_attr(feature = "serde", serde(with = "rgb_serde"))] Rgb<u8>),
+
+    /// Fade linearly from `start` in the top row to `end` in the bottom row.
+    Gradient {
+        #[cfg_attr(feature = "serde", serde(with = "rgb_serde"))]
+        start: Rgb<u8>,
+        #[cfg_attr(feature = "serde", serde(with = "rgb_serde"))]
+        end: Rgb<u8>,
+    },
+}
+
+/// The fill behind the painted cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Background {
+    /// One flat color.
+    Solid(#[cfg_attr(feature = "serde", serde(with = "rgb_serde"))] Rgb<u8>),
+
+    /// Alternating squares of `a` and `b`, each `cell` pixels wide, starting
+    /// with `a` in the top left corner. The squares line up with the image
+    /// rather than with the identicon's grid.
+    Checker {
+        #[cfg_attr(feature = "serde", serde(with = "rgb_serde"))]
+        a: Rgb<u8>,
+        #[cfg_attr(feature = "serde", serde(with = "rgb_serde"))]
+        b: Rgb<u8>,
+        cell: u32,
+    },
+
+    /// A gradient from `start` to `end` across the whole image, margins
+    /// included: from top to bottom when `vertical`, and otherwise from the
+    /// top left corner to the bottom right.
+    LinearGradient {
+        #[cfg_attr(feature = "serde", serde(with = "rgb_serde"))]
+        start: Rgb<u8>,
+        #[cfg_attr(feature = "serde", serde(with = "rgb_serde"))]
+        end: Rgb<u8>,
+        vertical: bool,
+    },
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Rgb([240, 240, 240]))
+    }
+}
+
+/// A preset combination of background and foreground colors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Theme {
+    /// GitHub's own look: muted colors on light gray. This is the default.
+    GitHubLight,
+
+    /// Brighter colors on GitHub's dark mode background.
+    GitHubDark,
+
+    /// Soft, light colors on white.
+    Pastel,
+
+    /// Strong, saturated colors on white.
+    Vivid,
+}
+
+impl Theme {
+    fn background(self) -> Rgb<u8> {
+        match self {
+            Theme::GitHubLight => Rgb([240, 240, 240]),
+            Theme::GitHubDark => Rgb([13, 17, 23]),
+            Theme::Pastel | Theme::Vivid => Rgb([255, 255, 255]),
+        }
+    }
+
+    // The (min, max) saturation and luminance, in percent.
+    fn ranges(self) -> ((u32, u32), (u32, u32)) {
+        match self {
+            Theme::GitHubLight => ((45, 65), (55, 75)),
+            Theme::GitHubDark => ((50, 70), (50, 65)),
+            Theme::Pastel => ((30, 50), (75, 85)),
+            Theme::Vivid => ((75, 95), (45, 55)),
+        }
+    }
+}
+
+/// The shape painted into each filled cell of the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CellShape {
+    /// Square cells, optionally with rounded corners. This is the default.
+    Square,
+
+    /// A circle inscribed in each cell.
+    Circle,
+
+    /// A regular hexagon with a point at the top and bottom, as tall as the
+    /// cell and centered across it, for honeycomb-like icons.
+    Hexagon,
+
+    /// A triangle pointing up, with its base along the bottom of the cell.
+    Triangle,
+
+    /// A square standing on one corner, touching the middle of each side.
+    Diamond,
+
+    /// A square, circle, triangle, or diamond in each cell, picked by nibbles
+    /// counting back from the end of the source, so the same source always
+    /// gets the same shapes. Mirrored cells match the ones they copy.
+    Hashed,
+}
+
+/// How the cells generated from the source are mirrored across the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Symmetry {
+    /// The left half is mirrored onto the right. This is the default.
+    Horizontal,
+
+    /// The top half is mirrored onto the bottom.
+    Vertical,
+
+    /// The top-left quadrant is mirrored into all four corners.
+    Quad,
+
+    /// The upper-right triangle is mirrored across the main diagonal.
+    Diagonal,
+
+    /// Every cell comes straight from the source, in row-major order.
+    ///
+    /// These patterns are less recognizable than mirrored ones, but pack in
+    /// more of the source's entropy. Cells past the end of the source are
+    /// left unpainted.
+    None,
+}
+
+/// The half of the grid that generates a mirrored pattern, which picks the
+/// axis it's mirrored across.
+///
+/// The other half is an exact copy, so there's no telling which side it came
+/// from, and this has no `Right` or `Bottom`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MirrorAxis {
+    /// The left half is mirrored onto the right, like `Symmetry::Horizontal`.
+    Left,
+
+    /// The top half is mirrored onto the bottom, like `Symmetry::Vertical`.
+    Top,
+}
+
+impl From<MirrorAxis> for Symmetry {
+    fn from(axis: MirrorAxis) -> Self {
+        match axis {
+            MirrorAxis::Left => Symmetry::Horizontal,
+            MirrorAxis::Top => Symmetry::Vertical,
+        }
+    }
+}
+
+/// Which compatibility mode should we generate an Identicon with.
+///
+/// More modes may be added in minor releases, so a `match` on a mode needs a
+/// `_ =>` arm.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Mode {
+    /// Generate GitHub-compatible emoticons. This is the default.
+    GitHub,
+
+    /// Identicon.js calculates colors differently.
+    IdenticonJS(IdenticonJSOptions),
+
+    /// GitHub's algorithm with adjustable colors. Saturation and luminance
+    /// start at their bases and the source lowers each by up to its range,
+    /// all in percent. `Mode::github_tuned()` gives GitHub's own values.
+    GitHubTuned {
+        base_sat: f32,
+        sat_range: f32,
+        base_lum: f32,
+        lum_range: f32,
+    },
+
+    /// Shades of gray, for print and monochrome displays: only a luminance,
+    /// from 20 to 70 percent, comes from the same bytes `Mode::GitHub` uses.
+    Grayscale,
+
+    /// Ethereum's blockies: a seeded random generator picks a foreground,
+    /// background, and spot color, and then the cells, with the left half of
+    /// the grid mirrored onto the right. The source is the seed, such as the
+    /// bytes of a lowercase address string. Density, symmetry, two tones, and
+    /// color bytes don't apply.
+    Blockies,
+
+    /// Colors from your own `ColorStrategy`, or a closure: see `Mode::custom`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn ColorStrategy>),
+}
+
+impl Mode {
+    /// `Mode::Custom` with a closure that picks the color for a source.
+    pub fn custom(f: impl Fn(&[u8]) -> Rgb<u8> + Send + Sync + 'static) -> Mode {
+        Mode::Custom(Arc::new(f))
+    }
+
+    /// `Mode::GitHubTuned` with the values `Mode::GitHub` uses, as a starting
+    /// point for adjustments.
+    pub fn github_tuned() -> Mode {
+        Mode::GitHubTuned {
+            base_sat: 65.0,
+            sat_range: 20.0,
+            base_lum: 75.0,
+            lum_range: 20.0,
+        }
+    }
+
+    // The number of source bytes the color computation reads from.
+    fn min_source_len(&self) -> usize {
+        match self {
+            Mode::GitHub | Mode::GitHubTuned { .. } | Mode::Grayscale => 16,
+            Mode::IdenticonJS(_) => 4,
+            Mode::Blockies | Mode::Custom(_) => 0,
+        }
+    }
+
+    // The four bytes of `source` the built-in modes read the color from.
+    fn color_key(&self, source: &[u8]) -> [u8; 4] {
+        // Note: Identicon.js uses the last bytes no matter how long the hash is.
+        let start = match self {
+            Mode::GitHub | Mode::GitHubTuned { .. } | Mode::Grayscale => 12,
+            Mode::IdenticonJS(_) => source.len() - 4,
+            // These read `source` themselves.
+            Mode::Blockies | Mode::Custom(_) => return [0; 4],
+        };
+        let mut key = [0; 4];
+        key.copy_from_slice(&source[start..start + 4]);
+        key
+    }
+
+    // The color for `source`, where `key` holds the bytes from `color_key`.
+    fn color(&self, source: &[u8], key: [u8; 4], theme: Theme) -> HSL {
+        match self {
+            Mode::GitHub => {
+                let ((sat_min, sat_max), (lum_min, lum_max)) = theme.ranges();
+                github_color(
+                    key,
+                    sat_max as f32,
+                    (sat_max - sat_min) as f32,
+                    lum_max as f32,
+                    (lum_max - lum_min) as f32,
+                )
+            }
+            Mode::GitHubTuned {
+                base_sat,
+                sat_range,
+                base_lum,
+                lum_range,
+            } => github_color(key, *base_sat, *sat_range, *base_lum, *lum_range),
+            Mode::IdenticonJS(options) => options.color(key),
+            Mode::Grayscale => HSL::new(0.0, 0.0, 70.0 - key[3] as f32 * (50.0 / 255.0)),
+            Mode::Blockies => Blockies::new(source, 0).color,
+            Mode::Custom(strategy) => HSL::from_rgb(strategy.foreground(source)),
+        }
+    }
+}
+
+/// Parses a built-in mode's name, ignoring case: `github`, `identicon-js`,
+/// `github-tuned`, `grayscale`, or `blockies`. The modes with options get
+/// their defaults.
+impl core::str::FromStr for Mode {
+    type Err = ModeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let modes = [
+            ("github", Mode::GitHub),
+            (
+                "identicon-js",
+                Mode::IdenticonJS(IdenticonJSOptions::default()),
+            ),
+            ("github-tuned", Mode::github_tuned()),
+            ("grayscale", Mode::Grayscale),
+            ("blockies", Mode::Blockies),
+        ];
+        IntoIterator::into_iter(modes)
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|(_, mode)| mode)
+            .ok_or_else(|| ModeParseError(s.into()))
+    }
+}
+
+/// The mode's name, as `FromStr` parses it, followed by any options in
+/// parentheses, such as `identicon-js (sat=0.7, bri=0.5)`.
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::GitHub => f.write_str("github"),
+            Mode::IdenticonJS(options) => {
+                write!(
+                    f,
+                    "identicon-js (sat={}, bri={}",
+                    options.saturation, options.brightness
+                )?;
+                if options.colors != 1 {
+                    write!(f, ", colors={}", options.colors)?;
+                }
+                f.write_str(")")
+            }
+            Mode::GitHubTuned {
+                base_sat,
+                sat_range,
+                base_lum,
+                lum_range,
+            } => write!(
+                f,
+                "github-tuned (sat={}-{}, lum={}-{})",
+                base_sat, sat_range, base_lum, lum_range
+            ),
+            Mode::Grayscale => f.write_str("grayscale"),
+            Mode::Blockies => f.write_str("blockies"),
+            Mode::Custom(_) => f.write_str("custom"),
+        }
+    }
+}
+
+/// Picks the foreground color for a source, for `Mode::Custom`.
+///
+/// `source` is the identicon's whole source, or just the `color_bytes` range
+/// when one is set. Return the same color for the same bytes every time.
+pub trait ColorStrategy: Send + Sync {
+    fn foreground(&self, source: &[u8]) -> Rgb<u8>;
+}
+
+// Lets `Mode` keep deriving these. Strategies are only equal to themselves.
+impl fmt::Debug for dyn ColorStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ColorStrategy")
+    }
+}
+
+impl PartialEq for dyn ColorStrategy {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(
+            self as *const Self as *const u8,
+            other as *const Self as *const u8,
+        )
+    }
+}
+
+/// Any closure from source bytes to a color is a strategy.
+impl<F: Fn(&[u8]) -> Rgb<u8> + Send + Sync> ColorStrategy for F {
+    fn foreground(&self, source: &[u8]) -> Rgb<u8> {
+        self(source)
+    }
+}
+
+/// The built-in modes work as strategies too, with the default theme.
+impl ColorStrategy for Mode {
+    fn foreground(&self, source: &[u8]) -> Rgb<u8> {
+        self.color(source, self.color_key(source), Theme::GitHubLight)
+            .rgb()
+    }
+}
+
+impl ColorStrategy for IdenticonJSOptions {
+    fn foreground(&self, source: &[u8]) -> Rgb<u8> {
+        let mut key = [0; 4];
+        key.copy_from_slice(&source[source.len() - 4..]);
+        self.color(key).rgb()
+    }
+}
+
+/// Identicon.js uses a constant saturation/brightness for generating icons.
+/// values between 0.0 and 1.0, inclusive. Use `new` to check them; anything
+/// outside is clamped when drawing.
+///
+/// More options may come, so start from `new` or `Default` and set fields
+/// from there rather than writing the struct out in full.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct IdenticonJSOptions {
+    pub saturation: f32,
+    pub brightness: f32,
+    /// How many hues to paint with, up to 4. The first is the usual one and
+    /// the rest are spaced evenly around the color wheel from it. Spare
+    /// nibbles after the pattern's pick a hue for each generated cell, so
+    /// mirrored cells match. Defaults to 1, as Identicon.js does; `two_tone`
+    /// takes precedence.
+    #[cfg_attr(feature = "serde", serde(default = "one"))]
+    pub colors: u8,
+}
+
+#[cfg(feature = "serde")]
+fn one() -> u8 {
+    1
+}
+
+impl IdenticonJSOptions {
+    /// Options with one hue, or `IdenticonError::OutOfRange` if `saturation`
+    /// or `brightness` isn't between 0.0 and 1.0.
+    pub fn new(saturation: f32, brightness: f32) -> Result<Self, IdenticonError> {
+        for &(option, value) in &[("saturation", saturation), ("brightness", brightness)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(IdenticonError::OutOfRange { option, value });
+            }
+        }
+        Ok(IdenticonJSOptions {
+            saturation,
+            brightness,
+            colors: 1,
+        })
+    }
+
+    // The hue comes from `key`, the last four bytes of the source.
+    fn color(&self, key: [u8; 4]) -> HSL {
+        // Use last 28 bits to determine the hue.
+        let mut h: u32 = key[0] as u32 & 0x0f;
+        h = h << 8 | (key[1] as u32);
+        h = h << 8 | (key[2] as u32);
+        h = h << 8 | (key[3] as u32);
+
+        let hue = map(h, 0, 0x0f_ff_ff_ff, 0, 360);
+        // Out of range, or NaN, is clamped rather than drawn as garbage.
+        let unit = |v: f32| if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) };
+        let sat = unit(self.saturation) * 100.0;
+        let lum = unit(self.brightness) * 100.0;
+
+        HSL::new(hue, sat, lum)
+    }
+}
+
+impl Default for IdenticonJSOptions {
+    fn default() -> Self {
+        // See: https://github.com/stewartlord/identicon.js/blob/4fad7cafec1b7a4d896015e084e861625ef5d64f/identicon.js#L30-L31
+        IdenticonJSOptions {
+            saturation: 0.7,
+            brightness: 0.5,
+            colors: 1,
+        }
+    }
+}
+
+/// Whether `a` and `b` produce the same identicon under `mode` with default
+/// settings: the same painted cells in the same color. Cheaper than rendering
+/// both, and stops early when the patterns already differ.
+pub fn same_image(a: &[u8], b: &[u8], mode: Mode) -> bool {
+    let first = Identicon::new(a).mode(mode.clone());
+    let second = Identicon::new(b).mode(mode);
+    first.pixel_iter().eq(second.pixel_iter())
+        && first.mode_color().rgb() == second.mode_color().rgb()
+}
+
+/// Parse a CSS-style hex color: `#rgb` or `#rrggbb`, with or without the
+/// `#`, in either case.
+pub fn parse_hex(s: &str) -> Result<Rgb<u8>, ColorParseError> {
+    let digits = s.strip_prefix('#').unwrap_or(s);
+    let mut values = [0u8; 6];
+    let mut len = 0;
+    for c in digits.chars() {
+        let value = c.to_digit(16).ok_or(ColorParseError::InvalidDigit(c))?;
+        if len < values.len() {
+            values[len] = value as u8;
+        }
+        len += 1;
+    }
+
+    match (len, values) {
+        // Each digit of the short form is doubled: `#fa0` is `#ffaa00`.
+        (3, [r, g, b, ..]) => Ok(Rgb([r * 17, g * 17, b * 17])),
+        (6, [r1, r2, g1, g2, b1, b2]) => Ok(Rgb([r1 << 4 | r2, g1 << 4 | g2, b1 << 4 | b2])),
+        _ => Err(ColorParseError::InvalidLength(len)),
+    }
+}
+
+// GitHub's color: the hue comes from `key`, and the saturation and luminance
+// drop below their bases by up to their ranges.
+fn github_color(key: [u8; 4], base_sat: f32, sat_range: f32, base_lum: f32, lum_range: f32) -> HSL {
+    // Use last 28 bits to determine HSL values.
+    let h1 = (key[0] as u16 & 0x0f) << 8;
+    let h2 = key[1] as u16;
+
+    let h = (h1 | h2) as u32;
+    let s = key[2] as f32;
+    let l = key[3] as f32;
+
+    let hue = map(h, 0, 4095, 0, 360);
+    let sat = s * (sat_range / 255.0);
+    let lum = l * (lum_range / 255.0);
+
+    HSL::new(hue, base_sat - sat, base_lum - lum)
+}
+
+// Formats a color the way CSS and SVG expect, e.g. `#f0f0f0`.
+fn hex(color: Rgb<u8>) -> String {
+    let Rgb([r, g, b]) = color;
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// XORs `bytes` together four at a time.
+fn fold_key(bytes: &[u8]) -> [u8; 4] {
+    let mut key = [0; 4];
+    for (ix, byte) in bytes.iter().enumerate() {
+        key[ix % 4] ^= byte;
+    }
+    key
+}
+
+// Linear interpolation between two colors, where `t` runs from 0.0 to 1.0.
+fn lerp(a: Rgb<u8>, b: Rgb<u8>, t: f32) -> Rgb<u8> {
+    let (Rgb([ar, ag, ab]), Rgb([br, bg, bb])) = (a, b);
+    let mix = |a: u8, b: u8| round(a as f32 + (b as f32 - a as f32) * t) as u8;
+    Rgb([mix(ar, br), mix(ag, bg), mix(ab, bb)])
+}
+
+// A cell's color as `cell_paints` works it out: fixed, partway between two
+// fixed colors, or derived from the source.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Paint {
+    Fixed(Rgb<u8>),
+    Mix(Rgb<u8>, Rgb<u8>, f32),
+    Derived(HSL),
+}
+
+impl Paint {
+    fn rgb(self) -> Rgb<u8> {
+        match self {
+            Paint::Fixed(color) => color,
+            Paint::Mix(a, b, t) => lerp(a, b, t),
+            Paint::Derived(color) => color.rgb(),
+        }
+    }
+}
+
+// https://processing.org/reference/map_.html
+//
+// Values outside `vmin..=vmax` extrapolate past the ends of the range, and
+// `dmax` may be the smaller end to map in reverse, so the subtractions happen
+// in `f32` rather than underflowing.
+fn map(value: u32, vmin: u32, vmax: u32, dmin: u32, dmax: u32) -> f32 {
+    let (value, vmin, vmax) = (value as f32, vmin as f32, vmax as f32);
+    let (dmin, dmax) = (dmin as f32, dmax as f32);
+    (value - vmin) * ((dmax - dmin) / (vmax - vmin)) + dmin
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        contrast_ratio, map, same_image, Background, Identicon, IdenticonError, IdenticonJSOptions,
+        MirrorAxis, Mode, Nibbler, Rgb, Symmetry, Theme, HSL,
+    };
+
+    // MD5 of "42".
+    pub const HASH: [u8; 16] = [
+        0xa1, 0xd0, 0xc6, 0xe8, 0x3f, 0x02, 0x73, 0x27, 0xd8, 0x46, 0x10, 0x63, 0xf4, 0xac, 0x58,
+        0xa6,
+    ];
+
+    #[test]
+    fn test_map() {
+        assert_eq!(20.0, map(0, 0, 100, 20, 120));
+
+        assert_eq!(120.0, map(100, 0, 100, 20, 120));
+    }
+
+    #[test]
+    fn test_map_out_of_range() {
+        assert_eq!(-10.0, map(0, 10, 100, 0, 90));
+        assert_eq!(0.0, map(10, 10, 100, 0, 90));
+        assert_eq!(100.0, map(110, 10, 100, 0, 90));
+        assert_eq!(20.0, map(100, 0, 100, 120, 20));
+    }
+
+    #[test]
+    fn it_renders_non_square_svg() {
+        let svg = Identicon::new(&HASH).dimensions(420, 210).svg();
+        assert!(svg.contains(r#"width="420" height="210" viewBox="0 0 420 210""#));
+        assert!(svg.contains(r##"<rect width="420" height="210" fill="#f0f0f0"/>"##));
+        let ix = Identicon::new(&HASH)
+            .pixels()
+            .iter()
+            .position(|p| *p)
+            .unwrap();
+        let (x, y) = (35 + ix % 5 * 70, 17 + ix / 5 * 35);
+        let rect = format!(r#"<rect x="{}" y="{}" width="70" height="35""#, x, y);
+        assert!(svg.contains(&rect));
+    }
+
+    #[test]
+    fn it_renders_svg() {
+        let svg = Identicon::new(&HASH).svg();
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="420""#));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains(r##"<rect width="420" height="420" fill="#f0f0f0"/>"##));
+
+        let painted = Identicon::new(&HASH)
+            .pixels()
+            .iter()
+            .filter(|p| **p)
+            .count();
+        assert_eq!(painted + 1, svg.matches("<rect").count());
+    }
+
+    #[test]
+    fn it_renders_checkered_svg() {
+        let (a, b) = (Rgb([255, 255, 255]), Rgb([204, 204, 204]));
+        let svg = Identicon::new(&HASH)
+            .background_fill(Background::Checker { a, b, cell: 8 })
+            .svg();
+        assert!(svg.contains(r#"<pattern id="checker" width="16" height="16""#));
+        assert!(svg.contains(r##"<rect x="8" width="8" height="8" fill="#cccccc"/>"##));
+        assert!(svg.contains(r#"<rect width="420" height="420" fill="url(#checker)"/>"#));
+    }
+
+    #[test]
+    fn it_renders_gradient_svg() {
+        let (start, end) = (Rgb([255, 0, 0]), Rgb([0, 0, 255]));
+        let gradient = |vertical| Background::LinearGradient {
+            start,
+            end,
+            vertical,
+        };
+        let svg = Identicon::new(&HASH).background_fill(gradient(true)).svg();
+        assert!(svg.contains(r#"<linearGradient id="gradient" x2="0" y2="1">"#));
+        assert!(svg.contains(r##"<stop offset="0" stop-color="#ff0000"/>"##));
+        assert!(svg.contains(r##"<stop offset="1" stop-color="#0000ff"/>"##));
+        assert!(svg.contains(r#"<rect width="420" height="420" fill="url(#gradient)"/>"#));
+
+        let svg = Identicon::new(&HASH).background_fill(gradient(false)).svg();
+        assert!(svg.contains(r#"<linearGradient id="gradient" x2="1" y2="1">"#));
+    }
+
+    #[test]
+    fn it_renders_ascii() {
+        for &n in &[5, 7] {
+            let identicon = Identicon::new(&HASH).grid_size(n);
+            let ascii = identicon.to_ascii();
+            let rows: Vec<_> = ascii.lines().collect();
+            assert_eq!(n as usize, rows.len());
+
+            let grid = identicon.pixel_grid();
+            for (row, line) in rows.iter().enumerate() {
+                let cells: Vec<char> = line.chars().collect();
+                assert_eq!(2 * n as usize, cells.len());
+                for (col, pair) in cells.chunks(2).enumerate() {
+                    let painted = grid[row * n as usize + col];
+                    assert_eq!(if painted { ['█'; 2] } else { [' '; 2] }, pair);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_renders_ansi() {
+        let color = Rgb([10, 20, 30]);
+        let identicon = Identicon::new(&HASH).foreground(color);
+        let ansi = identicon.to_ansi();
+        assert!(ansi.ends_with("\x1b[0m\n"));
+
+        let grid = identicon.pixel_grid();
+        let rows: Vec<_> = ansi.lines().collect();
+        assert_eq!(5, rows.len());
+        for (row, line) in rows.iter().enumerate() {
+            let line = line.strip_suffix("\x1b[0m").expect("reset");
+            let cells: Vec<_> = line.split("\x1b[48;2;").skip(1).collect();
+            assert_eq!(5, cells.len());
+            for (col, cell) in cells.iter().enumerate() {
+                let expected = if grid[row * 5 + col] {
+                    "10;20;30m  "
+                } else {
+                    "240;240;240m  "
+                };
+                assert_eq!(expected, *cell);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "grid size must be odd")]
+    fn it_rejects_even_grids() {
+        let _ = Identicon::new(&HASH).grid_size(6);
+    }
+
+    #[test]
+    fn it_rejects_short_sources() {
+        match Identicon::try_new(&[1, 2, 3, 4]) {
+            Err(IdenticonError::SourceTooShort { needed, got }) => {
+                assert_eq!(16, needed);
+                assert_eq!(4, got);
+            }
+            _ => panic!("expected SourceTooShort"),
+        }
+        assert!(Identicon::try_new(&HASH).is_ok());
+    }
+
+    #[test]
+    fn it_builds_from_a_config() {
+        let config = Identicon::builder()
+            .size(64)
+            .grid_size(7)
+            .mode(Mode::github_tuned())
+            .color_bytes(0..4);
+        let built = config.build(&HASH);
+        let direct = Identicon::new(&HASH)
+            .size(64)
+            .grid_size(7)
+            .mode(Mode::github_tuned())
+            .color_bytes(0..4);
+        assert_eq!(direct.svg(), built.svg());
+
+        // One config stamps out icons for different sources.
+        let other = [0xff; 16];
+        let tweaked = config.clone().symmetry(Symmetry::Quad);
+        assert_eq!(
+            Identicon::new(&other)
+                .size(64)
+                .grid_size(7)
+                .symmetry(Symmetry::Quad)
+                .pixel_grid(),
+            tweaked.build(&other).pixel_grid()
+        );
+        assert_eq!(
+            Identicon::new(&HASH).svg(),
+            Identicon::builder().build(&HASH).svg()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "color bytes")]
+    fn it_checks_config_color_bytes_on_build() {
+        let _ = Identicon::builder().color_bytes(12..20).build(&HASH);
+    }
+
+    #[test]
+    fn it_rejects_even_grids_without_panicking() {
+        match Identicon::new(&HASH).try_grid_size(6) {
+            Err(IdenticonError::InvalidGridSize(6)) => {}
+            _ => panic!("expected InvalidGridSize"),
+        }
+        let identicon = Identicon::new(&HASH).try_grid_size(7).unwrap();
+        assert_eq!(7, identicon.grid_size);
+        assert_eq!(
+            "grid size must be odd, got 6",
+            IdenticonError::InvalidGridSize(6).to_string()
+        );
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn it_hashes_strings() {
+        let pixels = Identicon::from_str("octocat").pixels();
+        let rows: Vec<String> = pixels
+            .chunks(5)
+            .map(|row| row.iter().map(|p| if *p { '#' } else { '.' }).collect())
+            .collect();
+        assert_eq!(vec!["..#..", "..#..", "#.#.#", ".....", ".###."], rows);
+    }
+
+    #[test]
+    fn it_picks_from_a_palette() {
+        let brand = vec![
+            Rgb([0xe6, 0x39, 0x46]),
+            Rgb([0xf1, 0xfa, 0xee]),
+            Rgb([0xa8, 0xda, 0xdc]),
+            Rgb([0x45, 0x7b, 0x9d]),
+            Rgb([0x1d, 0x35, 0x57]),
+            Rgb([0xff, 0xb7, 0x03]),
+        ];
+        let pick = |source: &[u8]| {
+            Identicon::new(source)
+                .palette(brand.clone())
+                .foreground_color()
+        };
+
+        assert_eq!(pick(&HASH), pick(&HASH));
+        assert!(brand.contains(&pick(&HASH)));
+        // The XOR of the 16 bytes, folded four at a time, picks the entry.
+        let index = u32::from_be_bytes(super::fold_key(&HASH)) as usize;
+        assert_eq!(brand[index % 6], pick(&HASH));
+
+        let picks: Vec<_> = (0..32u8).map(|i| pick(&[i, 0, 0, 0])).collect();
+        assert!(picks.iter().any(|&color| color != picks[0]));
+
+        let identicon = Identicon::new(&HASH).palette(brand.clone()).two_tone(true);
+        assert!(identicon
+            .cell_colors()
+            .iter()
+            .all(|&color| color == pick(&HASH)));
+        let fixed = Identicon::new(&HASH)
+            .palette(brand)
+            .foreground(Rgb([1, 2, 3]));
+        assert_eq!(Rgb([1, 2, 3]), fixed.foreground_color());
+
+        let err = Identicon::new(&HASH).try_palette(Vec::new()).unwrap_err();
+        assert!(matches!(err, IdenticonError::EmptyPalette));
+    }
+
+    #[test]
+    fn it_rebuilds_from_masks() {
+        let (fg, bg) = (Rgb([0, 0, 0]), Rgb([255, 255, 255]));
+        // The top left and bottom right corners, and the center.
+        let mask = 1 | 1 << 12 | 1 << 24;
+        let identicon = Identicon::from_mask(mask, fg, bg, 60);
+        let grid = identicon.pixel_grid();
+        assert_eq!(25, grid.len());
+        for (ix, &painted) in grid.iter().enumerate() {
+            assert_eq!([0, 12, 24].contains(&ix), painted);
+        }
+        assert_eq!((fg, bg), identicon.colors());
+
+        // High bits don't spill into anything.
+        assert_eq!(
+            grid,
+            Identicon::from_mask(mask | 1 << 31, fg, bg, 60).pixel_grid()
+        );
+        assert!(!Identicon::from_mask(0, fg, bg, 60)
+            .pixel_grid()
+            .contains(&true));
+
+        let generated = Identicon::new(&HASH).foreground(fg).background(bg);
+        let rebuilt = Identicon::from_mask(generated.pixel_mask(), fg, bg, 420);
+        assert_eq!(generated.pixel_grid(), rebuilt.pixel_grid());
+        assert_eq!(generated.svg(), rebuilt.svg());
+    }
+
+    #[test]
+    fn it_pins_pixel_masks() {
+        let mut reversed = HASH;
+        reversed.reverse();
+        let masks = [
+            Identicon::new(&HASH).pixel_mask(),
+            Identicon::new(&reversed).pixel_mask(),
+            Identicon::new(&[0; 16]).pixel_mask(),
+            Identicon::new(&[0xff; 16]).pixel_mask(),
+            Identicon::from_u64(42).pixel_mask(),
+            Identicon::new(&HASH).grid_size(3).pixel_mask(),
+        ];
+        // If these change, so does every icon drawn with the defaults.
+        let expected = [0x0152_2b7f, 0x004f_eeae, 0x01ff_ffff, 0, 0x000d_ed5b, 0x16f];
+        assert_eq!(expected, masks);
+    }
+
+    #[test]
+    #[should_panic(expected = "a 7x7 grid doesn't fit in a 32-bit mask")]
+    fn it_rejects_masks_of_large_grids() {
+        let _ = Identicon::new(&HASH).grid_size(7).pixel_mask();
+    }
+
+    #[test]
+    fn it_scales_by_whole_cells() {
+        let identicon = Identicon::new(&HASH).scale(2);
+        assert_eq!((24, 24), identicon.bounds());
+        assert_eq!(((4, 4), (2, 2)), identicon.layout());
+        for factor in 1..=10 {
+            let ((cell, _), (offset, _)) = Identicon::new(&HASH).scale(factor).layout();
+            assert_eq!((2 * factor as usize, factor as usize), (cell, offset));
+        }
+
+        let margin = Identicon::new(&HASH).grid_size(7).margin(3).scale(5);
+        assert_eq!((76, 76), margin.bounds());
+        assert_eq!(((10, 10), (3, 3)), margin.layout());
+        let ratio = Identicon::new(&HASH).margin_ratio(0.3).scale(2);
+        assert_eq!(((4, 4), (2, 2)), ratio.layout());
+
+        let huge = Identicon::new(&HASH).scale(u32::MAX / 4);
+        assert_eq!((u32::MAX, u32::MAX), huge.bounds());
+    }
+
+    #[test]
+    fn it_returns_both_colors() {
+        let identicon = Identicon::new(&HASH);
+        assert_eq!(
+            (identicon.foreground_color(), Rgb([240, 240, 240])),
+            identicon.colors()
+        );
+
+        let white = Rgb([255, 255, 255]);
+        let dark = identicon
+            .clone()
+            .foreground(Rgb([0, 0, 0]))
+            .background(white);
+        let (fg, bg) = dark.colors();
+        assert_eq!((Rgb([0, 0, 0]), white), (fg, bg));
+        assert!(contrast_ratio(fg, bg) > 20.9);
+
+        let checker = identicon.background_fill(Background::Checker {
+            a: white,
+            b: Rgb([0, 0, 0]),
+            cell: 4,
+        });
+        assert_eq!(white, checker.colors().1);
+    }
+
+    #[test]
+    #[should_panic(expected = "palette must have at least one color")]
+    fn it_rejects_empty_palettes() {
+        let _ = Identicon::new(&HASH).palette(Vec::new());
+    }
+
+    #[test]
+    fn it_inverts_the_pattern() {
+        let normal = Identicon::new(&HASH).pixel_grid();
+        let inverted = Identicon::new(&HASH).invert();
+        let expected: Vec<bool> = normal.iter().map(|painted| !painted).collect();
+        assert_eq!(expected, inverted.pixel_grid());
+        assert_eq!(normal, inverted.clone().invert().pixel_grid());
+        assert_eq!(
+            Identicon::new(&HASH).foreground_color(),
+            inverted.foreground_color()
+        );
+
+        let blockies = Identicon::new(&HASH).mode(Mode::Blockies);
+        let expected: Vec<bool> = blockies.pixel_grid().iter().map(|p| !p).collect();
+        assert_eq!(expected, blockies.invert().pixel_grid());
+    }
+
+    #[test]
+    fn it_picks_a_mirror_axis() {
+        let pixels = |axis| Identicon::new(&HASH).mirror_axis(axis).pixels();
+        let horizontal = Identicon::new(&HASH).pixels();
+        let vertical = Identicon::new(&HASH).symmetry(Symmetry::Vertical).pixels();
+        assert_eq!(horizontal, pixels(MirrorAxis::Left));
+        assert_eq!(vertical, pixels(MirrorAxis::Top));
+        assert_ne!(horizontal, vertical);
+    }
+
+    #[test]
+    fn it_mirrors_vertically() {
+        let horizontal = Identicon::new(&HASH).pixels();
+        let vertical = Identicon::new(&HASH).symmetry(Symmetry::Vertical).pixels();
+        for row in 0..5 {
+            for col in 0..5 {
+                assert_eq!(vertical[row * 5 + col], vertical[(4 - row) * 5 + col]);
+                // The same nibbles are consumed, just along the other axis.
+                assert_eq!(horizontal[row * 5 + col], vertical[col * 5 + row]);
+            }
+        }
+    }
+
+    #[test]
+    fn it_mirrors_into_quadrants() {
+        for &n in &[5, 7] {
+            let pixels = Identicon::new(&HASH)
+                .grid_size(n)
+                .symmetry(Symmetry::Quad)
+                .pixels();
+            let n = n as usize;
+            for row in 0..n {
+                for col in 0..n {
+                    let paint = pixels[row * n + col];
+                    assert_eq!(paint, pixels[row * n + (n - 1 - col)]);
+                    assert_eq!(paint, pixels[(n - 1 - row) * n + col]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_mirrors_diagonally() {
+        let pixels = Identicon::new(&HASH).symmetry(Symmetry::Diagonal).pixels();
+        for row in 0..5 {
+            for col in 0..5 {
+                assert_eq!(pixels[row * 5 + col], pixels[col * 5 + row]);
+            }
+        }
+    }
+
+    #[test]
+    fn it_fills_without_symmetry() {
+        let nibbles: Vec<bool> = Nibbler::new(&HASH).map(|x| x % 2 == 0).collect();
+        let pixels = Identicon::new(&HASH)
+            .grid_size(7)
+            .symmetry(Symmetry::None)
+            .pixels();
+        assert_eq!(&nibbles[..], &pixels[..32]);
+        assert!(pixels[32..].iter().all(|p| !p));
+    }
+
+    #[test]
+    fn it_ensures_contrast() {
+        // The icon's own color as the background is as low as contrast goes.
+        let color = Identicon::new(&HASH).mode_color().rgb();
+        let low = Identicon::new(&HASH).background(color);
+        assert_eq!(color, low.cell_colors()[0]);
+
+        let fixed = low.ensure_contrast(4.5).cell_colors()[0];
+        assert!(contrast_ratio(fixed, color) >= 4.5);
+        assert_eq!(
+            HSL::from_rgb(color).hue.round(),
+            HSL::from_rgb(fixed).hue.round()
+        );
+
+        // Colors that already contrast enough don't move.
+        let enough = Identicon::new(&HASH).ensure_contrast(1.2);
+        assert_eq!(color, enough.cell_colors()[0]);
+
+        // Nothing reaches 21 against mid gray, so take the best there is.
+        let gray = Rgb([119, 119, 119]);
+        let far = Identicon::new(&HASH)
+            .background(gray)
+            .ensure_contrast(21.0)
+            .cell_colors()[0];
+        assert_eq!(Rgb([0, 0, 0]), far);
+    }
+
+    #[test]
+    fn it_paints_in_grayscale() {
+        let gray = Identicon::new(&HASH).mode(Mode::Grayscale);
+        assert_eq!(Identicon::new(&HASH).pixel_grid(), gray.pixel_grid());
+
+        let Rgb([r, g, b]) = gray.cell_colors()[0];
+        assert_eq!((r, r), (g, b));
+        // The last byte, 0xa6, picks the luminance.
+        assert_eq!(96, r);
+
+        for last in [0x00, 0xff] {
+            let mut source = HASH;
+            source[15] = last;
+            let HSL { lum, .. } = Identicon::new(&source).mode(Mode::Grayscale).mode_color();
+            assert_eq!(if last == 0 { 70.0 } else { 20.0 }, lum);
+        }
+    }
+
+    #[test]
+    fn it_draws_blockies() {
+        let address = b"0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359";
+        let blockies = Identicon::new(address).mode(Mode::Blockies);
+        assert_eq!(8, blockies.grid_size);
+        assert!(blockies
+            .to_ascii()
+            .starts_with("██            ██\n                \n"));
+
+        // Spots get their own color, and the background is the seed's too.
+        let colors = blockies.cell_colors();
+        assert_eq!(Rgb([250, 173, 21]), colors[0]);
+        assert_ne!(colors[0], colors[25]);
+        match blockies.backdrop() {
+            Background::Solid(color) => assert_ne!(Background::default(), Background::Solid(color)),
+            fill => panic!("expected a solid background, got {:?}", fill),
+        }
+
+        let svg = blockies.clone().size(64).svg();
+        assert!(svg.contains(r#"<rect x="0" y="0" width="8" height="8""#));
+
+        // A background set by hand still wins.
+        let white = Rgb([255, 255, 255]);
+        let custom = Identicon::new(address)
+            .mode(Mode::Blockies)
+            .background(white);
+        assert_eq!(Background::Solid(white), custom.backdrop());
+    }
+
+    #[test]
+    fn it_picks_among_identicon_js_colors() {
+        use super::IdenticonJSOptions;
+
+        let js = |colors| {
+            Identicon::new(&HASH).mode(Mode::IdenticonJS(IdenticonJSOptions {
+                colors,
+                ..IdenticonJSOptions::default()
+            }))
+        };
+        let one = js(1).cell_colors();
+        assert!(one.iter().all(|&c| c == one[0]));
+        assert_eq!(one, js(0).cell_colors());
+
+        for count in 2..=4 {
+            let identicon = js(count);
+            let colors = identicon.cell_colors();
+            let distinct: std::collections::HashSet<_> = colors.iter().collect();
+            assert!(
+                distinct.len() > 1 && distinct.len() <= count as usize,
+                "{}",
+                count
+            );
+            assert!(distinct.contains(&one[0]));
+            // Mirrored cells share a color.
+            for row in colors.chunks(5) {
+                assert_eq!((row[0], row[1]), (row[4], row[3]));
+            }
+            assert_eq!(identicon.pixel_grid(), js(1).pixel_grid());
+        }
+        assert_eq!(js(4).cell_colors(), js(9).cell_colors());
+    }
+
+    #[test]
+    fn it_clones_and_debugs() {
+        let identicon = Identicon::new(&HASH).size(64).mode(Mode::github_tuned());
+        let tweaked = identicon.clone().grid_size(7);
+        assert_eq!(64, tweaked.size);
+        assert_eq!(5, identicon.grid_size);
+
+        let debug = format!("{:?}", identicon);
+        assert!(debug.starts_with("Identicon {"), "{}", debug);
+        assert!(debug.contains("GitHubTuned"), "{}", debug);
+        assert!(format!("{:?}", Identicon::builder()).starts_with("IdenticonConfig {"));
+
+        struct Red;
+        impl super::ColorStrategy for Red {
+            fn foreground(&self, _: &[u8]) -> Rgb<u8> {
+                Rgb([255, 0, 0])
+            }
+        }
+        let custom = Mode::Custom(super::Arc::new(Red));
+        assert_eq!("Custom(ColorStrategy)", format!("{:?}", custom));
+        assert_eq!(custom.clone(), custom);
+    }
+
+    #[test]
+    fn it_parses_modes() {
+        assert_eq!(Ok(Mode::GitHub), "github".parse());
+        assert_eq!(Ok(Mode::GitHub), "GitHub".parse());
+        assert_eq!(
+            Ok(Mode::IdenticonJS(Default::default())),
+            "Identicon-JS".parse()
+        );
+        assert_eq!(Ok(Mode::github_tuned()), "github-tuned".parse());
+        assert_eq!(Ok(Mode::Grayscale), "GRAYSCALE".parse());
+        assert_eq!(Ok(Mode::Blockies), "blockies".parse());
+
+        let err = "gitlab".parse::<Mode>().unwrap_err();
+        assert_eq!(super::ModeParseError("gitlab".into()), err);
+        assert!(err
+            .to_string()
+            .starts_with(r#"unknown mode "gitlab", expected github"#));
+    }
+
+    #[test]
+    fn it_displays_modes() {
+        for name in ["github", "grayscale", "blockies"] {
+            assert_eq!(name, name.parse::<Mode>().unwrap().to_string());
+        }
+        assert_eq!(
+            "identicon-js (sat=0.7, bri=0.5)",
+            Mode::IdenticonJS(Default::default()).to_string()
+        );
+        let options = IdenticonJSOptions {
+            colors: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            "identicon-js (sat=0.7, bri=0.5, colors=3)",
+            Mode::IdenticonJS(options).to_string()
+        );
+        assert_eq!(
+            "github-tuned (sat=65-20, lum=75-20)",
+            Mode::github_tuned().to_string()
+        );
+        assert_eq!("custom", Mode::custom(|_| Rgb([0, 0, 0])).to_string());
+    }
+
+    #[test]
+    fn it_checks_identicon_js_options() {
+        assert_eq!(
+            IdenticonJSOptions::default(),
+            IdenticonJSOptions::new(0.7, 0.5).unwrap()
+        );
+        let err = IdenticonJSOptions::new(0.7, 1.5).unwrap_err();
+        assert!(matches!(
+            err,
+            IdenticonError::OutOfRange {
+                option: "brightness",
+                value
+            } if value == 1.5
+        ));
+        assert_eq!("brightness is out of range, got 1.5", err.to_string());
+        assert!(IdenticonJSOptions::new(f32::NAN, 0.5).is_err());
+
+        // Built by hand, an out-of-range value draws as the nearest end.
+        let color = |saturation, brightness| {
+            let mode = Mode::IdenticonJS(IdenticonJSOptions {
+                saturation,
+                brightness,
+                colors: 1,
+            });
+            Identicon::new(&HASH).mode(mode).foreground_color()
+        };
+        assert_eq!(color(0.7, 1.0), color(0.7, 1.5));
+        assert_eq!(Rgb([255, 255, 255]), color(0.7, 1.5));
+        assert_eq!(color(0.0, 0.5), color(-2.0, 0.5));
+        assert_eq!(color(0.0, 0.5), color(f32::NAN, 0.5));
+    }
+
+    #[test]
+    fn it_seeds_from_numbers() {
+        assert_eq!(Identicon::from_u64(7).svg(), Identicon::from_u64(7).svg());
+
+        let icons: Vec<Identicon> = (0..3).map(Identicon::from_u64).collect();
+        for (a, b) in [(0, 1), (1, 2), (0, 2)] {
+            assert_ne!(icons[a].pixel_grid(), icons[b].pixel_grid());
+            assert_ne!(icons[a].mode_color(), icons[b].mode_color());
+        }
+        assert_eq!(16, icons[0].source.len());
+    }
+
+    #[test]
+    fn it_converts_from_byte_likes() {
+        let svg = Identicon::new(&HASH).svg();
+        assert_eq!(svg, Identicon::from(&HASH).svg());
+        assert_eq!(svg, Identicon::from(&HASH.to_vec()).svg());
+        assert_eq!(svg, Identicon::from(&HASH[..]).svg());
+
+        let login = "a login of 16+ bytes";
+        assert_eq!(
+            Identicon::new(login.as_bytes()).svg(),
+            Identicon::from(login).svg()
+        );
+        assert_eq!(
+            Identicon::new(login.as_bytes()).svg(),
+            Identicon::from(&login.to_string()).svg()
+        );
+    }
+
+    #[test]
+    fn it_owns_its_source() {
+        fn make(id: &str) -> Identicon<'static> {
+            let source = id.bytes().cycle().take(16).collect();
+            Identicon::owned(source).size(64)
+        }
+        let owned = make("abc");
+        let source: Vec<u8> = b"abc".iter().copied().cycle().take(16).collect();
+        assert_eq!(Identicon::new(&source).size(64).svg(), owned.svg());
+
+        let detached = {
+            let source = HASH.to_vec();
+            Identicon::new(&source).size(64).into_owned()
+        };
+        assert_eq!(Identicon::new(&HASH).size(64).svg(), detached.svg());
+    }
+
+    #[test]
+    fn it_salts_the_source() {
+        let plain = Identicon::new(&HASH);
+        let salted = Identicon::new(&HASH).salt(b"app one");
+        assert_ne!(plain.svg(), salted.svg());
+        assert_eq!(salted.svg(), Identicon::new(&HASH).salt(b"app one").svg());
+        assert_ne!(salted.svg(), Identicon::new(&HASH).salt(b"app two").svg());
+        assert_eq!(plain.svg(), Identicon::new(&HASH).salt(b"").svg());
+
+        // Long salts wrap around. Twenty bytes over sixteen XOR the first four
+        // twice, which cancels out.
+        let long = [1; 20];
+        let mut expected = HASH;
+        expected[4..].iter_mut().for_each(|b| *b ^= 1);
+        assert_eq!(&expected[..], &Identicon::new(&HASH).salt(&long).source[..]);
+
+        let config = Identicon::builder().salt(b"app one");
+        assert_eq!(salted.svg(), config.build(&HASH).svg());
+    }
+
+    #[test]
+    fn it_measures_contrast() {
+        let black = Rgb([0, 0, 0]);
+        let white = Rgb([255, 255, 255]);
+        let ratio = Identicon::new(&HASH)
+            .foreground(black)
+            .background(white)
+            .contrast_ratio();
+        assert!((21.0 - ratio).abs() < 1e-4);
+
+        let same = Identicon::new(&HASH).foreground(white).background(white);
+        assert_eq!(1.0, same.contrast_ratio());
+
+        // Against a checkerboard, the weaker square counts.
+        let checker = Background::Checker {
+            a: white,
+            b: Rgb([200, 200, 200]),
+            cell: 8,
+        };
+        let ratio = Identicon::new(&HASH)
+            .foreground(Rgb([200, 200, 200]))
+            .background_fill(checker)
+            .contrast_ratio();
+        assert_eq!(1.0, ratio);
+
+        let adjusted = Identicon::new(&HASH).background(white).ensure_contrast(7.0);
+        assert!(adjusted.contrast_ratio() >= 7.0);
+    }
+
+    #[test]
+    fn it_paints_two_tones() {
+        let one = Identicon::new(&HASH).cell_colors();
+        let two = Identicon::new(&HASH).two_tone(true).cell_colors();
+        let pixels = Identicon::new(&HASH).pixels();
+        for row in 0..5 {
+            for col in 0..5 {
+                assert_eq!(two[row * 5 + col], two[row * 5 + 4 - col]);
+            }
+        }
+
+        let mut painted: Vec<Rgb<u8>> =
+            (0..25).filter(|&ix| pixels[ix]).map(|ix| two[ix]).collect();
+        painted.sort_by_key(|c| c.0);
+        painted.dedup();
+        assert_eq!(2, painted.len());
+        assert!(painted.contains(&one[0]));
+    }
+
+    #[test]
+    fn it_sets_svg_opacity() {
+        assert!(!Identicon::new(&HASH).svg().contains("fill-opacity"));
+        let svg = Identicon::new(&HASH).opacity(0.25).svg();
+        assert!(
+            svg.contains(r##"fill="#82d666" fill-opacity="0.25"/>"##),
+            "{}",
+            svg
+        );
+    }
+
+    #[test]
+    fn it_fills_with_a_closure() {
+        let even = Identicon::new(&HASH).fill_fn(|x| x % 2 == 0);
+        assert_eq!(Identicon::new(&HASH).pixel_grid(), even.pixel_grid());
+
+        let all = Identicon::new(&HASH).fill_fn(|_| true);
+        assert!(all.pixel_grid().iter().all(|&p| p));
+        let sparse = Identicon::new(&HASH).fill_fn(|x| x < 4).pixel_grid();
+        let dense = Identicon::new(&HASH).fill_fn(|x| x >= 4).pixel_grid();
+        assert!(sparse.iter().zip(&dense).all(|(a, b)| a != b));
+
+        // The last of density and fill_fn wins.
+        let dense_again = Identicon::new(&HASH).fill_fn(|_| false).density(1.0);
+        assert_eq!(all.pixel_grid(), dense_again.pixel_grid());
+        let config = Identicon::builder().density(1.0).fill_fn(|_| false);
+        assert!(config.build(&HASH).pixel_grid().iter().all(|&p| !p));
+    }
+
+    #[test]
+    fn it_controls_density() {
+        let count = |density| {
+            Identicon::new(&HASH)
+                .symmetry(Symmetry::None)
+                .density(density)
+                .pixels()
+                .iter()
+                .filter(|p| **p)
+                .count()
+        };
+        assert_eq!(0, count(0.0));
+        assert_eq!(25, count(1.0));
+        assert!(count(0.25) <= count(0.5));
+        assert!(count(0.5) <= count(0.75));
+    }
+
+    #[test]
+    fn it_iterates_cells() {
+        let identicon = Identicon::new(&HASH).grid_size(7);
+        let grid = identicon.pixel_grid();
+        let cells: Vec<(u32, u32, bool)> = identicon.cells().collect();
+        assert_eq!(49, cells.len());
+        for (ix, &(row, col, painted)) in cells.iter().enumerate() {
+            assert_eq!(ix as u32, row * 7 + col);
+            assert_eq!(grid[ix], painted);
+        }
+    }
+
+    #[test]
+    fn it_tunes_github_colors() {
+        let github = Identicon::new(&HASH);
+        let tuned = Identicon::new(&HASH).mode(Mode::github_tuned());
+        assert_eq!(github.cell_colors(), tuned.cell_colors());
+        assert_eq!(github.svg(), tuned.svg());
+
+        let vivid = Identicon::new(&HASH).mode(Mode::GitHubTuned {
+            base_sat: 100.0,
+            sat_range: 0.0,
+            base_lum: 50.0,
+            lum_range: 0.0,
+        });
+        let hue = github.mode_color().hue;
+        assert_eq!(HSL::new(hue, 100.0, 50.0), vivid.mode_color());
+    }
+
+    #[test]
+    fn it_reads_color_from_separate_bytes() {
+        let default = Identicon::new(&HASH);
+        let same = Identicon::new(&HASH).color_bytes(12..16);
+        assert_eq!(default.cell_colors(), same.cell_colors());
+
+        let apart = Identicon::new(&HASH).color_bytes(8..16);
+        assert_eq!(default.pixel_grid(), apart.pixel_grid());
+        assert_ne!(default.cell_colors(), apart.cell_colors());
+
+        // Folding the range down to four bytes: 8..12 XOR 12..16.
+        let mut folded = HASH;
+        for ix in 12..16 {
+            folded[ix] ^= HASH[ix - 4];
+        }
+        let expected = Identicon::new(&folded);
+        assert_eq!(expected.cell_colors(), apart.cell_colors());
+    }
+
+    #[test]
+    #[should_panic(expected = "color bytes")]
+    fn it_rejects_short_color_bytes() {
+        let _ = Identicon::new(&HASH).color_bytes(14..16);
+    }
+
+    #[test]
+    fn it_compares_sources() {
+        assert!(same_image(&HASH, &HASH, Mode::GitHub));
+
+        // Bytes past the ones GitHub's algorithm reads don't matter.
+        let mut longer = HASH.to_vec();
+        longer.extend_from_slice(&[1, 2, 3]);
+        assert!(same_image(&HASH, &longer, Mode::GitHub));
+
+        let mut recolored = HASH;
+        recolored[15] ^= 0xff;
+        assert!(!same_image(&HASH, &recolored, Mode::GitHub));
+
+        let mut reshaped = HASH;
+        reshaped[0] ^= 0x01;
+        assert!(!same_image(&HASH, &reshaped, Mode::GitHub));
+    }
+
+    #[test]
+    fn it_uses_custom_color_strategies() {
+        use super::{Arc, ColorStrategy, IdenticonJSOptions};
+
+        // Red or blue, by the first byte it's given.
+        struct Brand;
+        impl ColorStrategy for Brand {
+            fn foreground(&self, source: &[u8]) -> Rgb<u8> {
+                if source[0].is_multiple_of(2) {
+                    Rgb([200, 0, 0])
+                } else {
+                    Rgb([0, 0, 200])
+                }
+            }
+        }
+
+        let custom = Identicon::new(&HASH).mode(Mode::Custom(Arc::new(Brand)));
+        assert_eq!(Identicon::new(&HASH).pixel_grid(), custom.pixel_grid());
+        assert!(custom.cell_colors().iter().all(|c| *c == Rgb([0, 0, 200])));
+
+        // The strategy sees only the color bytes when set. 0xd0 is even.
+        let ranged = Identicon::new(&HASH)
+            .mode(Mode::Custom(Arc::new(Brand)))
+            .color_bytes(1..5);
+        assert!(ranged.cell_colors().iter().all(|c| *c == Rgb([200, 0, 0])));
+
+        // The built-in modes are strategies too.
+        let github = Identicon::new(&HASH).mode_color().rgb();
+        assert_eq!(github, Mode::GitHub.foreground(&HASH));
+        let options = IdenticonJSOptions::default();
+        let js = Identicon::new(&HASH).mode(Mode::IdenticonJS(IdenticonJSOptions::default()));
+        assert_eq!(js.mode_color().rgb(), options.foreground(&HASH));
+
+        assert_ne!(Mode::GitHub, custom.mode);
+        assert_eq!(custom.mode, custom.mode);
+    }
+
+    #[test]
+    fn it_uses_custom_color_closures() {
+        let teal = Identicon::new(&HASH).mode(Mode::custom(|_| Rgb([0, 128, 128])));
+        assert!(teal.cell_colors().iter().all(|c| *c == Rgb([0, 128, 128])));
+
+        // Say, a palette keyed on the first byte.
+        const PALETTE: [Rgb<u8>; 2] = [Rgb([10, 20, 30]), Rgb([40, 50, 60])];
+        let palette = Mode::custom(|source| PALETTE[source[0] as usize % 2]);
+        let identicon = Identicon::new(&HASH).mode(palette);
+        assert_eq!(PALETTE[1], identicon.foreground_color());
+        assert_eq!(Identicon::new(&HASH).pixel_grid(), identicon.pixel_grid());
+    }
+
+    #[test]
+    fn it_limits_generating_columns() {
+        let all = Identicon::new(&HASH).generating_columns(3);
+        assert_eq!(Identicon::new(&HASH).pixel_grid(), all.pixel_grid());
+
+        // The center column is generated, and every other one copies it.
+        let one = Identicon::new(&HASH).generating_columns(1).pixel_grid();
+        for row in one.chunks(5) {
+            assert!(row.iter().all(|&painted| painted == row[2]));
+        }
+
+        // Two columns in the middle, then the outer one reflects back.
+        let n = 9;
+        let two = Identicon::new(&HASH).grid_size(n).generating_columns(2);
+        let grid = two.pixel_grid();
+        let full = Identicon::new(&HASH).grid_size(n).pixel_grid();
+        for row in grid.chunks(n as usize) {
+            assert_eq!(row[4 - 2], row[4 - 1]);
+            assert_eq!(row[4 - 3], row[4]);
+            assert_eq!(row[4 - 4], row[4]);
+        }
+        assert_eq!(full[4], grid[4]);
+        assert_eq!(full[3], grid[3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "generating columns must be from 1 to 3")]
+    fn it_rejects_too_many_generating_columns() {
+        let _ = Identicon::new(&HASH).generating_columns(4);
+    }
+
+    #[test]
+    fn it_parses_hex_colors() {
+        use super::{parse_hex, ColorParseError};
+
+        let cases = [
+            ("#fff", Ok(Rgb([255, 255, 255]))),
+            ("#ffffff", Ok(Rgb([255, 255, 255]))),
+            ("ffffff", Ok(Rgb([255, 255, 255]))),
+            ("#a1B2c3", Ok(Rgb([0xa1, 0xb2, 0xc3]))),
+            ("#fa0", Ok(Rgb([0xff, 0xaa, 0x00]))),
+            ("", Err(ColorParseError::InvalidLength(0))),
+            ("#ffff", Err(ColorParseError::InvalidLength(4))),
+            ("#fffffff", Err(ColorParseError::InvalidLength(7))),
+            ("##fff", Err(ColorParseError::InvalidDigit('#'))),
+            ("#ffg", Err(ColorParseError::InvalidDigit('g'))),
+            ("#ff ", Err(ColorParseError::InvalidDigit(' '))),
+        ];
+        for (input, expected) in &cases {
+            assert_eq!(*expected, parse_hex(input), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn it_applies_themes() {
+        let default = Identicon::new(&HASH);
+        let light = Identicon::new(&HASH).theme(Theme::GitHubLight);
+        assert_eq!(default.svg(), light.svg());
+
+        let dark = Identicon::new(&HASH).theme(Theme::GitHubDark);
+        assert!(dark.svg().contains(r##"fill="#0d1117""##));
+
+        let themes = [Theme::GitHubDark, Theme::Pastel, Theme::Vivid];
+        for &theme in &themes {
+            let themed = Identicon::new(&HASH).theme(theme);
+            assert_eq!(default.pixel_grid(), themed.pixel_grid());
+            assert_ne!(default.cell_colors(), themed.cell_colors());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_modes() {
+        use super::{Foreground, IdenticonJSOptions};
+
+        let json = serde_json::to_string(&Mode::GitHub).unwrap();
+        assert_eq!(r#""GitHub""#, json);
+        assert_eq!(Mode::GitHub, serde_json::from_str(&json).unwrap());
+
+        let mode = Mode::IdenticonJS(IdenticonJSOptions {
+            saturation: 0.25,
+            brightness: 0.75,
+            colors: 3,
+        });
+        let json = serde_json::to_string(&mode).unwrap();
+        assert_eq!(mode, serde_json::from_str(&json).unwrap());
+
+        // Options from before `colors` existed still load.
+        let old = r#"{"IdenticonJS":{"saturation":0.7,"brightness":0.5}}"#;
+        let expected = Mode::IdenticonJS(IdenticonJSOptions::default());
+        assert_eq!(expected, serde_json::from_str(old).unwrap());
+
+        let fill = Foreground::Gradient {
+            start: Rgb([1, 2, 3]),
+            end: Rgb([4, 5, 6]),
+        };
+        let json = serde_json::to_string(&fill).unwrap();
+        assert_eq!(fill, serde_json::from_str(&json).unwrap());
+    }
 }
@@ -28,7 +28,7 @@ fn generate(input: &[u8]) -> Result<()> {
     let encoder = PngEncoder::new(output);
     encoder
         .encode(image.as_ref(), width, height, ColorType::Rgb8)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        .map_err(io::Error::other)
 }
 
 fn hash() -> Result<[u8; 16]> {
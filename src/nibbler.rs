@@ -1,15 +1,54 @@
-use std::slice::Iter;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
 
+/// Splits bytes into 4-bit nibbles, the way identicons read their source:
+/// the high nibble of each byte and then the low one.
+///
+/// ```
+/// use identicon::Nibbler;
+///
+/// assert_eq!(vec![0x0a, 0x0b, 0x0c, 0x0d], Nibbler::new(&[0xab, 0xcd]).collect::<Vec<_>>());
+/// ```
 pub struct Nibbler<'a> {
     byte: Option<u8>,
-    bytes: Iter<'a, u8>,
+    bytes: Cow<'a, [u8]>,
+    // The index of the next byte to split.
+    next: usize,
+    low_first: bool,
 }
 
 impl<'a> Nibbler<'a> {
-    pub fn new(bytes: &[u8]) -> Nibbler {
+    pub fn new(bytes: &[u8]) -> Nibbler<'_> {
+        Nibbler::from_cow(Cow::Borrowed(bytes))
+    }
+
+    /// Like `new`, but taking ownership of `bytes`, so the nibbler can be
+    /// stored without tying it to a borrow.
+    pub fn owned(bytes: Vec<u8>) -> Nibbler<'static> {
+        Nibbler::from_cow(Cow::Owned(bytes))
+    }
+
+    fn from_cow(bytes: Cow<'a, [u8]>) -> Nibbler<'a> {
         Nibbler {
-            bytes: bytes.iter(),
+            bytes,
+            next: 0,
             byte: None,
+            low_first: false,
+        }
+    }
+
+    /// Like `new`, but with the low nibble of each byte before the high one,
+    /// as some other implementations read them.
+    ///
+    /// ```
+    /// use identicon::Nibbler;
+    ///
+    /// assert_eq!(vec![0x0b, 0x0a], Nibbler::new_low_first(&[0xab]).collect::<Vec<_>>());
+    /// ```
+    pub fn new_low_first(bytes: &[u8]) -> Nibbler<'_> {
+        Nibbler {
+            low_first: true,
+            ..Nibbler::new(bytes)
         }
     }
 }
@@ -23,12 +62,14 @@ impl<'a> Iterator for Nibbler<'a> {
                 self.byte = None;
                 Some(value)
             }
-            None => match self.bytes.next() {
+            None => match self.bytes.get(self.next) {
                 Some(value) => {
-                    let hi = *value & 0xf0;
+                    self.next += 1;
+                    let hi = (*value & 0xf0) >> 4;
                     let lo = *value & 0x0f;
-                    self.byte = Some(lo);
-                    Some(hi >> 4)
+                    let (first, second) = if self.low_first { (lo, hi) } else { (hi, lo) };
+                    self.byte = Some(second);
+                    Some(first)
                 }
                 None => None,
             },
@@ -47,4 +88,21 @@ mod tests {
         let result: Vec<u8> = nibbles.collect();
         assert_eq!(vec![0x02, 0x0a], result);
     }
+
+    #[test]
+    fn it_owns_its_bytes() {
+        let nibbler = {
+            let bytes = vec![0x2a, 0xf0];
+            Nibbler::owned(bytes)
+        };
+        let result: Vec<u8> = nibbler.collect();
+        assert_eq!(vec![0x02, 0x0a, 0x0f, 0x00], result);
+    }
+
+    #[test]
+    fn it_iterates_low_nibbles_first() {
+        let bytes = vec![0x2a, 0xf0];
+        let result: Vec<u8> = Nibbler::new_low_first(&bytes).collect();
+        assert_eq!(vec![0x0a, 0x02, 0x00, 0x0f], result);
+    }
 }
@@ -0,0 +1,2150 @@
+//! Raster rendering through the `image` crate.
+
+use std::io;
+use std::mem;
+use std::path::Path;
+
+use image::{
+    codecs::png::PngEncoder, ColorType, DynamicImage, GrayImage, ImageBuffer, Luma, Pixel,
+    RgbImage, RgbaImage,
+};
+
+use crate::contrast::LINEAR;
+use crate::hsl::round;
+#[cfg(feature = "gif")]
+use crate::HSL;
+use crate::{
+    Background, CellShape, Foreground, Identicon, IdenticonError, Paint, RenderError, Rgb, Rgba,
+    Theme,
+};
+
+/// The most images `Identicon::to_ico` packs into one file.
+#[cfg(feature = "ico")]
+pub const MAX_ICO_SIZES: usize = 16;
+
+impl<'a> Identicon<'a> {
+    pub fn image(&self) -> RgbImage {
+        self.draw(&self.canvas(), |color| color, |color| color)
+    }
+
+    /// Render with a fully transparent background, for placing the icon on
+    /// top of other content.
+    pub fn image_rgba(&self) -> RgbaImage {
+        self.draw(
+            &self.canvas(),
+            |Rgb([r, g, b])| Rgba([r, g, b, 0]),
+            |Rgb([r, g, b])| Rgba([r, g, b, 255]),
+        )
+    }
+
+    /// Render the same pattern for a light and a dark UI, as `(light, dark)`:
+    /// on the backgrounds of `Theme::GitHubLight` and `Theme::GitHubDark`,
+    /// with the colors from each theme. Colors from the source also get at
+    /// least the 3.0 contrast ratio WCAG asks of graphics, unless a different
+    /// `ensure_contrast` is set.
+    pub fn theme_pair(&self) -> (RgbImage, RgbImage) {
+        let render = |theme| {
+            let icon = self.clone().theme(theme);
+            match self.min_contrast {
+                Some(_) => icon.image(),
+                None => icon.ensure_contrast(3.0).image(),
+            }
+        };
+        (render(Theme::GitHubLight), render(Theme::GitHubDark))
+    }
+
+    /// Render as usual, background included, and then cut out the largest
+    /// circle that fits, for round avatars: everything outside it is
+    /// transparent. With `antialias` the edge has partial alpha, and
+    /// otherwise it's hard.
+    pub fn circle_crop(&self) -> RgbaImage {
+        let opaque = |Rgb([r, g, b])| Rgba([r, g, b, 255]);
+        let mut image = self.draw(&self.canvas(), opaque, opaque);
+        let (width, height) = image.dimensions();
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        let radius = cx.min(cy);
+        let samples = self.samples as u32;
+        let inside = |x: f32, y: f32| (x - cx).powi(2) + (y - cy).powi(2) <= radius * radius;
+
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let mut covered = 0;
+            for sy in 0..samples {
+                for sx in 0..samples {
+                    let px = x as f32 + (sx as f32 + 0.5) / samples as f32;
+                    let py = y as f32 + (sy as f32 + 0.5) / samples as f32;
+                    covered += inside(px, py) as u32;
+                }
+            }
+            pixel[3] = round(covered as f32 * 255.0 / (samples * samples) as f32) as u8;
+        }
+        image
+    }
+
+    /// Render with 16 bits per channel, for pipelines that keep working at
+    /// that depth. Colors worked out from the source, `ensure_contrast` and
+    /// `two_tone` included, convert straight to 16 bits rather than being
+    /// rounded to 8, and gradients, blended edges, `opacity`, `adjust`, and
+    /// `tint` are all mixed at 16 bits. Fixed colors are widened as they are.
+    pub fn image_rgb16(&self) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+        self.draw(&self.canvas(), |color| color, |color| color)
+    }
+
+    /// Render in pure black and white for 1-bit displays: painted cells,
+    /// strokes, and any `frame` are 0 and everything else is 255. This skips
+    /// working out the colors, and ignores `antialias`, `opacity`, `adjust`,
+    /// and `tint` so that no grays creep in.
+    pub fn image_luma(&self) -> GrayImage {
+        let identicon = Identicon {
+            samples: 1,
+            opacity: 1.0,
+            adjust: (0.0, 1.0),
+            tint: None,
+            foreground: Some(Foreground::Solid(Rgb([0, 0, 0]))),
+            ..self.clone()
+        };
+        identicon.draw(&identicon.canvas(), |_| Luma([255]), |_| Luma([0]))
+    }
+
+    /// Like `image()`, wrapped for code that works across color types.
+    pub fn dynamic_image(&self) -> DynamicImage {
+        DynamicImage::ImageRgb8(self.image())
+    }
+
+    /// The image as `(width, height, pixels)`, where `pixels` holds tightly
+    /// packed RGB triples, one byte per channel, row by row from the top left
+    /// corner. The same bytes as `image()`, without its buffer type.
+    pub fn to_rgb_raw(&self) -> (u32, u32, Vec<u8>) {
+        let (width, height) = self.bounds();
+        (width, height, self.image().into_raw())
+    }
+
+    /// Write out a PNG file, streaming it into `writer`, such as a file or
+    /// an HTTP response body, rather than building it in memory first.
+    pub fn to_png<W: io::Write>(&self, writer: &mut W) -> Result<(), IdenticonError> {
+        let img = self.image();
+        let encoder = PngEncoder::new(writer);
+        let (w, h) = img.dimensions();
+        encoder.encode(img.as_ref(), w, h, ColorType::Rgb8)?;
+        Ok(())
+    }
+
+    /// Write the image to a file, in the format its extension names, such as
+    /// `.png`. Formats the `image` crate wasn't built with give an error.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), IdenticonError> {
+        self.image().save(path)?;
+        Ok(())
+    }
+
+    /// Encode as an in-memory PNG file.
+    pub fn to_png_bytes(&self) -> Result<Vec<u8>, IdenticonError> {
+        let mut bytes = Vec::new();
+        self.to_png(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Encode as a JPEG file at `quality` from 1 to 100, clamped to that
+    /// range. JPEG blurs the hard edges between cells a little, and it has no
+    /// transparency, so there's no RGBA version.
+    #[cfg(feature = "jpeg")]
+    pub fn to_jpeg_bytes(&self, quality: u8) -> Result<Vec<u8>, IdenticonError> {
+        use image::codecs::jpeg::JpegEncoder;
+
+        let img = self.image();
+        let (width, height) = img.dimensions();
+        let mut bytes = Vec::new();
+        let mut encoder = JpegEncoder::new_with_quality(&mut bytes, quality.clamp(1, 100));
+        encoder.encode(img.as_ref(), width, height, ColorType::Rgb8)?;
+        Ok(bytes)
+    }
+
+    /// Encode as a lossless WebP file, which for flat colors like these is
+    /// usually smaller than PNG.
+    #[cfg(feature = "webp")]
+    pub fn to_webp_bytes(&self) -> Result<Vec<u8>, IdenticonError> {
+        use image::error::{EncodingError, ImageError, ImageFormatHint};
+        use image::ImageFormat;
+        use image_webp::WebPEncoder;
+
+        let img = self.image();
+        let (width, height) = img.dimensions();
+        let mut bytes = Vec::new();
+        WebPEncoder::new(&mut bytes)
+            .encode(img.as_ref(), width, height, image_webp::ColorType::Rgb8)
+            .map_err(|err| {
+                let format = ImageFormatHint::Exact(ImageFormat::WebP);
+                ImageError::Encoding(EncodingError::new(format, err))
+            })?;
+        Ok(bytes)
+    }
+
+    /// Encode as an ICO file holding an RGBA PNG of the icon at each of
+    /// `sizes`, for a `favicon.ico` from which browsers pick the best fit.
+    /// ICO allows sizes from 1 to 256, and this takes up to `MAX_ICO_SIZES` of
+    /// them.
+    #[cfg(feature = "ico")]
+    pub fn to_ico(&self, sizes: &[u32]) -> Result<Vec<u8>, IdenticonError> {
+        use image::error::{ImageError, ParameterError, ParameterErrorKind};
+
+        if sizes.is_empty() || sizes.len() > MAX_ICO_SIZES {
+            let message = format!("expected 1 to {} ICO sizes", MAX_ICO_SIZES);
+            let kind = ParameterErrorKind::Generic(message);
+            return Err(ImageError::Parameter(ParameterError::from_kind(kind)).into());
+        }
+        if let Some(size) = sizes.iter().find(|&&size| !(1..=256).contains(&size)) {
+            let message = format!("ICO sizes must be from 1 to 256, got {}", size);
+            let kind = ParameterErrorKind::Generic(message);
+            return Err(ImageError::Parameter(ParameterError::from_kind(kind)).into());
+        }
+
+        let images = sizes
+            .iter()
+            .map(|&size| {
+                let mut png = Vec::new();
+                let img = self.clone().size(size).image_rgba();
+                PngEncoder::new(&mut png).encode(img.as_ref(), size, size, ColorType::Rgba8)?;
+                Ok(png)
+            })
+            .collect::<Result<Vec<_>, IdenticonError>>()?;
+
+        // The ICONDIR header, then an entry per image, then the images.
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&[0, 0, 1, 0]);
+        bytes.extend_from_slice(&(sizes.len() as u16).to_le_bytes());
+        let mut offset = 6 + 16 * sizes.len();
+        for (&size, png) in sizes.iter().zip(&images) {
+            // A size of 256 is written as 0.
+            let side = size as u8;
+            bytes.extend_from_slice(&[side, side, 0, 0]);
+            bytes.extend_from_slice(&1u16.to_le_bytes());
+            bytes.extend_from_slice(&32u16.to_le_bytes());
+            bytes.extend_from_slice(&(png.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += png.len();
+        }
+        for png in images {
+            bytes.extend_from_slice(&png);
+        }
+        Ok(bytes)
+    }
+
+    /// Encode as a `data:` URI, for embedding straight into an `<img src>`.
+    #[cfg(feature = "data-uri")]
+    pub fn data_uri(&self) -> Result<String, IdenticonError> {
+        use base64::Engine;
+
+        let png = self.to_png_bytes()?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(png);
+        Ok(format!("data:image/png;base64,{}", encoded))
+    }
+
+    /// Draw the icon into `buf` with its top left corner at `(x, y)`, such as
+    /// onto a sprite atlas. Pixels outside the icon are left alone.
+    pub fn render_into(&self, buf: &mut RgbImage, x: u32, y: u32) -> Result<(), IdenticonError> {
+        let (width, height) = buf.dimensions();
+        let size = self.bounds();
+        let fits =
+            |at: u32, len: u32, room: u32| at.checked_add(len).is_some_and(|end| end <= room);
+        if !fits(x, size.0, width) || !fits(y, size.1, height) {
+            return Err(RenderError::OutOfBounds {
+                x,
+                y,
+                size,
+                width,
+                height,
+            }
+            .into());
+        }
+
+        if size.0 == 0 || size.1 == 0 {
+            return Ok(());
+        }
+
+        let canvas = self.canvas();
+        let mut scratch = Vec::new();
+        let stride = width as usize * 3;
+        let (x, y) = (x as usize, y as usize);
+        let (icon_width, icon_height) = (size.0 as usize, size.1 as usize);
+        for (row, line) in buf.chunks_mut(stride).skip(y).take(icon_height).enumerate() {
+            let line = &mut line[x * 3..(x + icon_width) * 3];
+            self.draw_row(&canvas, row, line, |c| c, |c| c, &mut scratch);
+        }
+        Ok(())
+    }
+
+    /// Like `image()`, but renders rows of pixels in parallel with rayon.
+    /// The output is identical; this only pays off for large sizes.
+    #[cfg(feature = "rayon")]
+    pub fn image_parallel(&self) -> RgbImage {
+        use rayon::prelude::*;
+
+        let canvas = self.canvas();
+        let (width, height) = self.bounds();
+        let mut image = RgbImage::new(width, height);
+        if width == 0 || height == 0 {
+            return image;
+        }
+        image
+            .par_chunks_mut(width as usize * 3)
+            .enumerate()
+            .for_each_init(Vec::new, |scratch, (y, line)| {
+                self.draw_row(&canvas, y, line, |color| color, |color| color, scratch)
+            });
+        image
+    }
+
+    /// Encode as a looping animated GIF of `frames` frames, 100ms apart, in
+    /// which the foreground hue turns a full circle while the pattern holds
+    /// still. The first frame matches `image()`.
+    #[cfg(feature = "gif")]
+    pub fn to_gif(&self, frames: u32) -> Result<Vec<u8>, IdenticonError> {
+        use image::codecs::gif::{GifEncoder, Repeat};
+        use image::{Delay, Frame};
+
+        let frames = frames.max(1);
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut bytes);
+            encoder.set_repeat(Repeat::Infinite)?;
+            for frame in 0..frames {
+                let mut canvas = self.canvas();
+                if frame > 0 {
+                    let degrees = 360.0 * frame as f32 / frames as f32;
+                    for color in canvas.colors.iter_mut() {
+                        *color = Paint::Derived(HSL::from_rgb(color.rgb()).rotate(degrees));
+                    }
+                }
+
+                let opaque = |Rgb([r, g, b])| Rgba([r, g, b, 255]);
+                let image = self.draw(&canvas, opaque, opaque);
+                let delay = Delay::from_numer_denom_ms(100, 1);
+                encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    // Renders `canvas`, converting colors with `background` for the fill
+    // behind the cells and with `paint` for everything else.
+    fn draw<S: Channel, P: Pixel<Subpixel = S> + 'static>(
+        &self,
+        canvas: &Canvas<S>,
+        background: impl Fn(Rgb<S>) -> P,
+        paint: impl Fn(Rgb<S>) -> P,
+    ) -> ImageBuffer<P, Vec<S>> {
+        let (width, height) = self.bounds();
+        let mut image = ImageBuffer::new(width, height);
+        self.draw_into(canvas, &mut image, background, paint, &mut Vec::new());
+        image
+    }
+
+    // Like `draw`, but into an existing image of the right size.
+    fn draw_into<S: Channel, P: Pixel<Subpixel = S> + 'static>(
+        &self,
+        canvas: &Canvas<S>,
+        image: &mut ImageBuffer<P, Vec<S>>,
+        background: impl Fn(Rgb<S>) -> P,
+        paint: impl Fn(Rgb<S>) -> P,
+        scratch: &mut Vec<S>,
+    ) {
+        // `chunks_mut` can't split rows of nothing.
+        if image.width() == 0 || image.height() == 0 {
+            return;
+        }
+        let width = image.width() as usize * P::CHANNEL_COUNT as usize;
+        for (y, line) in image.chunks_mut(width).enumerate() {
+            self.draw_row(canvas, y, line, &background, &paint, scratch);
+        }
+    }
+
+    // The shape of every cell, in row-major order.
+    fn cell_shapes(&self) -> Vec<CellShape> {
+        let n = self.grid_size as usize;
+        if self.cell_shape != CellShape::Hashed {
+            return vec![self.cell_shape; n * n];
+        }
+
+        let shapes = [
+            CellShape::Square,
+            CellShape::Circle,
+            CellShape::Triangle,
+            CellShape::Diamond,
+        ];
+        let len = self.source.len() * 2;
+        (0..n * n)
+            .map(|ix| match len {
+                0 => CellShape::Square,
+                len => {
+                    let g = self.generator(ix / n, ix % n);
+                    let nibble = self.nibble(len - 1 - g % len).unwrap_or(0);
+                    shapes[nibble as usize % shapes.len()]
+                }
+            })
+            .collect()
+    }
+
+    // Lookup tables for `adjust` and then `tint`, one per color channel, or
+    // `None` when they change nothing.
+    fn levels<S: Channel>(&self) -> Option<[Vec<S>; 3]> {
+        let (brightness, contrast) = self.adjust;
+        if brightness == 0.0 && contrast == 1.0 && self.tint.is_none() {
+            return None;
+        }
+        let mut levels: [Vec<S>; 3] = Default::default();
+        for (channel, table) in levels.iter_mut().enumerate() {
+            *table = (0..=S::MAX as usize)
+                .map(|value| {
+                    let mut v = (value as f32 / S::MAX - 0.5) * contrast + 0.5 + brightness;
+                    v = round(v.clamp(0.0, 1.0) * S::MAX);
+                    if let Some((Rgb(color), strength)) = self.tint {
+                        let c = S::widen(color[channel]);
+                        v = if self.linear_blending {
+                            let v = S::from_f32(v).linear();
+                            encode::<S>(v + (c.linear() - v) * strength).value()
+                        } else {
+                            round(v + (c.value() - v) * strength)
+                        };
+                    }
+                    S::from_f32(v)
+                })
+                .collect();
+        }
+        Some(levels)
+    }
+
+    // The layout and colors, scaled up by the number of samples.
+    fn canvas<S: Channel>(&self) -> Canvas<S> {
+        self.canvas_reusing(Vec::new())
+    }
+
+    // The canvas, with its cells in `pixels` so a buffer from an earlier
+    // canvas can be reused.
+    fn canvas_reusing<S: Channel>(&self, mut pixels: Vec<bool>) -> Canvas<S> {
+        pixels.clear();
+        pixels.extend(self.pixel_iter());
+        let ((cell_width, cell_height), (x, y)) = self.layout();
+        let (width, height) = self.bounds();
+        let scale = self.samples as usize;
+        Canvas {
+            scale,
+            size: (width as usize * scale, height as usize * scale),
+            cell: (cell_width * scale, cell_height * scale),
+            offset: (x * scale, y * scale),
+            // Drawing clamps the radius to the cell, so it can saturate here.
+            corner_radius: self.corner_radius.saturating_mul(scale as u32),
+            pixels,
+            colors: self.cell_paints(),
+            shapes: self.cell_shapes(),
+            background: self.backdrop(),
+            opacity: self.opacity,
+            linear: self.linear_blending,
+            levels: self.levels(),
+        }
+    }
+
+    // Renders row `y` of the image into `line`. When supersampling, the rows
+    // of samples are drawn into `scratch` first and then averaged down.
+    fn draw_row<S: Channel, P: Pixel<Subpixel = S> + 'static>(
+        &self,
+        canvas: &Canvas<S>,
+        y: usize,
+        line: &mut [S],
+        background: impl Fn(Rgb<S>) -> P,
+        paint: impl Fn(Rgb<S>) -> P,
+        scratch: &mut Vec<S>,
+    ) {
+        let scale = canvas.scale;
+        let channels = P::CHANNEL_COUNT as usize;
+        let alpha = P::COLOR_TYPE.has_alpha();
+        if scale == 1 {
+            self.draw_line(canvas, y, line, background, &paint);
+        } else {
+            let width = line.len() * scale;
+            scratch.clear();
+            scratch.resize(width * scale, S::widen(0));
+            for (dy, samples) in scratch.chunks_mut(width).enumerate() {
+                self.draw_line(canvas, y * scale + dy, samples, &background, &paint);
+            }
+            downsample(scratch, line, scale, channels, alpha, canvas.linear);
+        }
+
+        if let Some(levels) = &canvas.levels {
+            let colors = if alpha { channels - 1 } else { channels };
+            for pixel in line.chunks_exact_mut(channels) {
+                for (c, levels) in pixel[..colors].iter_mut().zip(levels) {
+                    *c = levels[c.index()];
+                }
+            }
+        }
+
+        if let Some((width, color)) = self.frame {
+            let color = paint(widen(color));
+            let (w, h) = (canvas.size.0 / scale, canvas.size.1 / scale);
+            let framed = |ix: usize, len: usize| {
+                let width = (width as usize).min(len);
+                ix < width || ix >= len - width
+            };
+            for (x, pixel) in line.chunks_exact_mut(channels).enumerate() {
+                if framed(y, h) || framed(x, w) {
+                    pixel.copy_from_slice(color.channels());
+                }
+            }
+        }
+    }
+
+    // Draws line `y` of the canvas into `line`, which holds its subpixels.
+    fn draw_line<S: Channel, P: Pixel<Subpixel = S> + 'static>(
+        &self,
+        canvas: &Canvas<S>,
+        y: usize,
+        line: &mut [S],
+        background: impl Fn(Rgb<S>) -> P,
+        paint: impl Fn(Rgb<S>) -> P,
+    ) {
+        self.fill_background(canvas, y, line, background);
+        if canvas.cell.0 == 0 || canvas.cell.1 == 0 {
+            return;
+        }
+
+        self.fill_line(canvas, y, line, &paint);
+        if let Some((width, color)) = self.stroke {
+            let width = width.saturating_mul(canvas.scale as u32);
+            self.stroke_line(canvas, y, line, width, paint(widen(color)));
+        }
+    }
+
+    fn fill_background<S: Channel, P: Pixel<Subpixel = S> + 'static>(
+        &self,
+        canvas: &Canvas<S>,
+        y: usize,
+        line: &mut [S],
+        background: impl Fn(Rgb<S>) -> P,
+    ) {
+        let pixels = line.chunks_exact_mut(P::CHANNEL_COUNT as usize);
+        match canvas.background {
+            Background::Solid(color) => {
+                let color = background(widen(color));
+                pixels.for_each(|pixel| pixel.copy_from_slice(color.channels()));
+            }
+            Background::Checker { a, b, cell } => {
+                let cell = cell.max(1) as usize * canvas.scale;
+                let (a, b) = (background(widen(a)), background(widen(b)));
+                let odd_row = y / cell % 2 == 1;
+                for (x, pixel) in pixels.enumerate() {
+                    let color = if (x / cell % 2 == 1) == odd_row { a } else { b };
+                    pixel.copy_from_slice(color.channels());
+                }
+            }
+            Background::LinearGradient {
+                start,
+                end,
+                vertical,
+            } => {
+                let (start, end) = (widen(start), widen(end));
+                let last = |len: usize| len.saturating_sub(1) as f32;
+                let (right, bottom) = (last(canvas.size.0), last(canvas.size.1));
+                if vertical {
+                    let color = background(mix(start, end, y as f32 / bottom.max(1.0)));
+                    pixels.for_each(|pixel| pixel.copy_from_slice(color.channels()));
+                } else {
+                    for (x, pixel) in pixels.enumerate() {
+                        let t = (x + y) as f32 / (right + bottom).max(1.0);
+                        pixel.copy_from_slice(background(mix(start, end, t)).channels());
+                    }
+                }
+            }
+        }
+    }
+
+    fn fill_line<S: Channel, P: Pixel<Subpixel = S> + 'static>(
+        &self,
+        canvas: &Canvas<S>,
+        y: usize,
+        line: &mut [S],
+        paint: impl Fn(Rgb<S>) -> P,
+    ) {
+        let channels = P::CHANNEL_COUNT as usize;
+        let n = self.grid_size as usize;
+        let Canvas {
+            cell: (cell_width, cell_height),
+            offset: (x, offset),
+            ..
+        } = *canvas;
+        if y < offset || (y - offset) / cell_height >= n {
+            return;
+        }
+
+        let row = (y - offset) / cell_height;
+        let dy = y - offset - row * cell_height;
+        for col in 0..n {
+            let ix = col + row * n;
+            if !canvas.pixels[ix] {
+                continue;
+            }
+
+            let foreground = paint(S::paint(canvas.colors[ix]));
+            let foreground = foreground.channels();
+            let alpha = P::COLOR_TYPE.has_alpha();
+            let fill = |pixel: &mut [S]| match canvas.opacity {
+                opacity if opacity >= 1.0 => pixel.copy_from_slice(foreground),
+                opacity => blend(pixel, foreground, opacity, alpha, canvas.linear),
+            };
+
+            let x0 = (col * cell_width + x) * channels;
+            let cell = &mut line[x0..x0 + cell_width * channels];
+            let pixels = cell.chunks_exact_mut(channels);
+            let shape = canvas.shapes[ix];
+            if shape == CellShape::Square && canvas.corner_radius == 0 {
+                pixels.for_each(fill);
+            } else {
+                pixels
+                    .enumerate()
+                    .filter(|&(dx, _)| self.covers(canvas, shape, dx, dy))
+                    .for_each(|(_, pixel)| fill(pixel));
+            }
+        }
+    }
+
+    // Strokes straddle the cell's edge, reaching into neighboring cells and
+    // the margin. Neighbors' strokes cover the same pixels where they meet.
+    fn stroke_line<S: Channel, P: Pixel<Subpixel = S> + 'static>(
+        &self,
+        canvas: &Canvas<S>,
+        y: usize,
+        line: &mut [S],
+        width: u32,
+        color: P,
+    ) {
+        let channels = P::CHANNEL_COUNT as usize;
+        let n = self.grid_size as usize;
+        let Canvas {
+            cell: (cell_width, cell_height),
+            offset: (left, top),
+            ..
+        } = *canvas;
+        let half = width as f32 / 2.0;
+        let reach = width as usize / 2 + 1;
+
+        for (ix, _) in canvas.pixels.iter().enumerate().filter(|(_, p)| **p) {
+            let (row, col) = (ix / n, ix % n);
+            let dy = y as f32 + 0.5 - (row * cell_height + top) as f32;
+            if dy < -half || dy >= cell_height as f32 + half {
+                continue;
+            }
+
+            let x0 = col * cell_width + left;
+            let x1 = (x0 + cell_width + reach).min(line.len() / channels);
+            for x in x0.saturating_sub(reach)..x1 {
+                let dx = x as f32 + 0.5 - x0 as f32;
+                let contains = |grow| self.contains(canvas, canvas.shapes[ix], dx, dy, grow);
+                if contains(half) && !contains(-half) {
+                    line[x * channels..(x + 1) * channels].copy_from_slice(color.channels());
+                }
+            }
+        }
+    }
+
+    // Whether `shape` covers the pixel at `(dx, dy)` within the cell.
+    fn covers<S: Channel>(
+        &self,
+        canvas: &Canvas<S>,
+        shape: CellShape,
+        dx: usize,
+        dy: usize,
+    ) -> bool {
+        // Measure from the pixel's center.
+        self.contains(canvas, shape, dx as f32 + 0.5, dy as f32 + 0.5, 0.0)
+    }
+
+    // Whether the point `(px, py)`, relative to the cell's top left corner,
+    // lies in `shape` after growing its outline by `grow` pixels.
+    fn contains<S: Channel>(
+        &self,
+        canvas: &Canvas<S>,
+        shape: CellShape,
+        px: f32,
+        py: f32,
+        grow: f32,
+    ) -> bool {
+        let (width, height) = canvas.cell;
+        // The other shapes are worked out in a square as wide as the cell and
+        // stretched to its height.
+        let size = width as f32;
+        let stretched = py * (size / height as f32);
+        match shape {
+            // `cell_shapes` has already picked a shape for each hashed cell.
+            CellShape::Square | CellShape::Hashed => {
+                let inside = |p: f32, len: usize| -grow <= p && p < len as f32 + grow;
+                // Distance to the center of the nearest rounded corner.
+                let radius = canvas.corner_radius.min(width.min(height) as u32 / 2) as f32;
+                let (right, bottom) = (width as f32 - radius, height as f32 - radius);
+                let dx = (radius - px).max(px - right).max(0.0);
+                let dy = (radius - py).max(py - bottom).max(0.0);
+                let reach = (radius + grow).max(0.0);
+                inside(px, width) && inside(py, height) && dx * dx + dy * dy <= reach * reach
+            }
+            CellShape::Circle => {
+                // The circle inscribed in the cell.
+                let radius = size / 2.0;
+                let (dx, dy) = (px - radius, stretched - radius);
+                let reach = (radius + grow).max(0.0);
+                dx * dx + dy * dy <= reach * reach
+            }
+            CellShape::Hexagon => {
+                // Inside all three pairs of opposite edges, whose normals
+                // point across the flat sides at 0, 60, and 120 degrees.
+                let radius = size / 2.0;
+                let (dx, dy) = (px - radius, stretched - radius);
+                let reach = radius * SIN_60 + grow;
+                let (x, y) = (dx / 2.0, dy * SIN_60);
+                dx.abs() <= reach && (x + y).abs() <= reach && (x - y).abs() <= reach
+            }
+            CellShape::Triangle => {
+                // On the inner side of each edge, going clockwise from the
+                // apex.
+                let corners = [(size / 2.0, 0.0), (size, size), (0.0, size)];
+                (0..3).all(|i| {
+                    let ((ax, ay), (bx, by)) = (corners[i], corners[(i + 1) % 3]);
+                    let (ex, ey) = (bx - ax, by - ay);
+                    let cross = ex * (stretched - ay) - ey * (px - ax);
+                    cross / (ex * ex + ey * ey).sqrt() >= -grow
+                })
+            }
+            CellShape::Diamond => {
+                let radius = size / 2.0;
+                let (dx, dy) = ((px - radius).abs(), (stretched - radius).abs());
+                dx + dy <= radius + grow * core::f32::consts::SQRT_2
+            }
+        }
+    }
+}
+
+const SIN_60: f32 = 0.866_025_4;
+
+/// Lay out `icons` in a grid `cols` wide, such as for a contact sheet. Each
+/// icon keeps its own size, in a slot as wide as the widest one and as tall
+/// as the tallest, and any space left over shows the default background color.
+/// Returns `IdenticonError::OutOfRange` if the sheet would be more than
+/// `u32::MAX` pixels wide or tall.
+pub fn sprite_sheet(icons: &[Identicon], cols: u32) -> Result<RgbImage, IdenticonError> {
+    let cols = cols.max(1);
+    let slot = |side: fn((u32, u32)) -> u32| {
+        icons
+            .iter()
+            .map(|icon| side(icon.bounds()))
+            .max()
+            .unwrap_or(0)
+    };
+    let (slot_width, slot_height) = (slot(|(w, _)| w), slot(|(_, h)| h));
+    let rows = (icons.len() as u32).div_ceil(cols);
+    let used = (icons.len() as u32).min(cols);
+
+    let side = |option, count: u32, slot: u32| {
+        count.checked_mul(slot).ok_or(IdenticonError::OutOfRange {
+            option,
+            value: count as f32 * slot as f32,
+        })
+    };
+    let width = side("sprite sheet width", used, slot_width)?;
+    let height = side("sprite sheet height", rows, slot_height)?;
+
+    let background = Rgb([240, 240, 240]);
+    let mut sheet = RgbImage::from_pixel(width, height, background);
+    for (ix, icon) in icons.iter().enumerate() {
+        let (row, col) = (ix as u32 / cols, ix as u32 % cols);
+        icon.render_into(&mut sheet, col * slot_width, row * slot_height)?;
+    }
+    Ok(sheet)
+}
+
+/// Draw your own pattern the way `image()` draws a generated one: `grid` is
+/// `cols` cells wide and tall, in row-major order, with the same cell size
+/// and margins as a `cols` grid identicon `size` pixels square.
+///
+/// # Panics
+///
+/// If `cols` is 0 or `grid` doesn't have `cols * cols` cells.
+pub fn render_grid(grid: &[bool], cols: u32, size: u32, fg: Rgb<u8>, bg: Rgb<u8>) -> RgbImage {
+    // Counted in `u64` so that wide grids can't overflow.
+    let cells = cols as u64 * cols as u64;
+    assert!(
+        cols > 0 && grid.len() as u64 == cells,
+        "a grid {} cells wide needs {} cells, got {}",
+        cols,
+        cells,
+        grid.len()
+    );
+    Identicon::with_mask(grid.to_vec(), cols)
+        .size(size)
+        .foreground(fg)
+        .background(bg)
+        .image()
+}
+
+/// Renders identicons one after another into the same image buffer, instead
+/// of allocating a new one for each like `Identicon::image()` does.
+#[derive(Debug, Default)]
+pub struct Renderer {
+    image: RgbImage,
+    scratch: Vec<u8>,
+    pixels: Vec<bool>,
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer::default()
+    }
+
+    /// Render `icon` the same as `icon.image()`. The buffer is reused as long
+    /// as the size stays the same as the previous icon's.
+    pub fn render(&mut self, icon: &Identicon) -> &RgbImage {
+        let (width, height) = icon.bounds();
+        if self.image.dimensions() != (width, height) {
+            self.image = RgbImage::new(width, height);
+        }
+        let canvas = icon.canvas_reusing(mem::take(&mut self.pixels));
+        icon.draw_into(&canvas, &mut self.image, |c| c, |c| c, &mut self.scratch);
+        self.pixels = canvas.pixels;
+        &self.image
+    }
+}
+
+// The layout and colors shared by every row of the image, for drawing with
+// `S` subpixels.
+struct Canvas<S> {
+    // Samples per pixel along each axis.
+    scale: usize,
+    // The image's width and height, and then the cell size and the offset of
+    // the first cell along each axis, in samples.
+    size: (usize, usize),
+    cell: (usize, usize),
+    offset: (usize, usize),
+    corner_radius: u32,
+    pixels: Vec<bool>,
+    colors: Vec<Paint>,
+    shapes: Vec<CellShape>,
+    background: Background,
+    opacity: f32,
+    // Whether to blend in linear light.
+    linear: bool,
+    // What each color channel's values become after `adjust` and `tint`, if
+    // anything changes.
+    levels: Option<[Vec<S>; 3]>,
+}
+
+// The subpixel types raster output is drawn in, with what drawing needs to
+// convert and mix them.
+trait Channel: image::Primitive + 'static {
+    // The brightest value.
+    const MAX: f32;
+
+    // An 8-bit value at this depth.
+    fn widen(value: u8) -> Self;
+
+    // A cell's color at this depth. Colors derived from the source convert
+    // straight to it rather than through 8 bits.
+    fn paint(paint: Paint) -> Rgb<Self>;
+
+    // The value as an index into tables like `Canvas::levels`.
+    fn index(self) -> usize;
+
+    // The nearest value to `value`, which runs from 0.0 to `MAX`.
+    fn from_f32(value: f32) -> Self;
+
+    // The value in linear light, from 0.0 to 1.0.
+    fn linear(self) -> f32;
+
+    fn value(self) -> f32 {
+        self.index() as f32
+    }
+}
+
+impl Channel for u8 {
+    const MAX: f32 = 255.0;
+
+    fn widen(value: u8) -> u8 {
+        value
+    }
+
+    fn paint(paint: Paint) -> Rgb<u8> {
+        paint.rgb()
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn from_f32(value: f32) -> u8 {
+        round(value) as u8
+    }
+
+    fn linear(self) -> f32 {
+        LINEAR[self as usize]
+    }
+}
+
+impl Channel for u16 {
+    const MAX: f32 = 65535.0;
+
+    fn widen(value: u8) -> u16 {
+        value as u16 * 257
+    }
+
+    fn paint(paint: Paint) -> Rgb<u16> {
+        match paint {
+            Paint::Fixed(color) => widen(color),
+            Paint::Mix(a, b, t) => mix(widen(a), widen(b), t),
+            Paint::Derived(color) => color.rgb16(),
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn from_f32(value: f32) -> u16 {
+        round(value) as u16
+    }
+
+    // The same curve `LINEAR` is worked out from.
+    fn linear(self) -> f32 {
+        let v = self as f32 / 65535.0;
+        if v <= 0.040_45 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    }
+}
+
+// An 8-bit color at the depth of `S`.
+fn widen<S: Channel>(Rgb(color): Rgb<u8>) -> Rgb<S> {
+    Rgb(color.map(S::widen))
+}
+
+// Linear interpolation between two colors, where `t` runs from 0.0 to 1.0.
+// In 8 bits this is the same as `lerp`.
+fn mix<S: Channel>(Rgb(a): Rgb<S>, Rgb(b): Rgb<S>, t: f32) -> Rgb<S> {
+    let mix = |i: usize| S::from_f32(a[i].value() + (b[i].value() - a[i].value()) * t);
+    Rgb([mix(0), mix(1), mix(2)])
+}
+
+// Composites `color` over `pixel` at `opacity`. With an alpha channel this is
+// the usual "over" operator, so a transparent pixel takes on `color` as it is.
+fn blend<S: Channel>(pixel: &mut [S], color: &[S], opacity: f32, alpha: bool, linear: bool) {
+    if !alpha {
+        for (value, &c) in pixel.iter_mut().zip(color) {
+            *value = if linear {
+                let v = value.linear();
+                encode(v + (c.linear() - v) * opacity)
+            } else {
+                S::from_f32(value.value() + (c.value() - value.value()) * opacity)
+            };
+        }
+        return;
+    }
+
+    let last = pixel.len() - 1;
+    let top = color[last].value() / S::MAX * opacity;
+    let bottom = pixel[last].value() / S::MAX * (1.0 - top);
+    let out = top + bottom;
+    if out > 0.0 {
+        for (value, &c) in pixel[..last].iter_mut().zip(&color[..last]) {
+            *value = if linear {
+                let (v, c) = (value.linear(), c.linear());
+                encode((c * top + v * bottom) / out)
+            } else {
+                S::from_f32((c.value() * top + value.value() * bottom) / out)
+            };
+        }
+    }
+    pixel[last] = S::from_f32(out * S::MAX);
+}
+
+// Averages each `scale` by `scale` block of `samples`, which holds `scale`
+// lines of the supersampled image, into one pixel of `line`. Colors are
+// weighted by alpha so that a transparent background doesn't tint the edges.
+fn downsample<S: Channel>(
+    samples: &[S],
+    line: &mut [S],
+    scale: usize,
+    channels: usize,
+    alpha: bool,
+    linear: bool,
+) {
+    let width = line.len() * scale;
+    let colors = if alpha { channels - 1 } else { channels };
+    let count = (scale * scale) as u64;
+    let average = |sum: u64, n: u64| S::from_f32(((sum + n / 2) / n) as f32);
+
+    for (x, pixel) in line.chunks_exact_mut(channels).enumerate() {
+        let (mut sums, mut linear_sums, mut weight) = ([0u64; 4], [0f32; 4], 0);
+        for dy in 0..scale {
+            let at = dy * width + x * scale * channels;
+            for sample in samples[at..at + scale * channels].chunks_exact(channels) {
+                let w = if alpha {
+                    sample[colors].index() as u64
+                } else {
+                    1
+                };
+                if linear {
+                    for (sum, &value) in linear_sums.iter_mut().zip(&sample[..colors]) {
+                        *sum += value.linear() * w as f32;
+                    }
+                } else {
+                    for (sum, &value) in sums.iter_mut().zip(&sample[..colors]) {
+                        *sum += value.index() as u64 * w;
+                    }
+                }
+                weight += w;
+            }
+        }
+
+        if weight == 0 {
+            // Fully transparent: keep the background's color as it is.
+            pixel.copy_from_slice(&samples[x * scale * channels..][..channels]);
+            continue;
+        }
+        if linear {
+            for (value, &sum) in pixel[..colors].iter_mut().zip(&linear_sums) {
+                *value = encode(sum / weight as f32);
+            }
+        } else {
+            for (value, &sum) in pixel[..colors].iter_mut().zip(&sums) {
+                *value = average(sum, weight);
+            }
+        }
+        if alpha {
+            pixel[colors] = average(weight, count);
+        }
+    }
+}
+
+// A value in linear light back to the sRGB channel value, the inverse of
+// `Channel::linear`.
+fn encode<S: Channel>(linear: f32) -> S {
+    let v = linear.clamp(0.0, 1.0);
+    let v = if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    S::from_f32(v * S::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use image::RgbImage;
+
+    use crate::tests::HASH;
+    use crate::{
+        Background, CellShape, Foreground, Identicon, IdenticonError, Mode, Renderer, Rgb, Rgba,
+        Theme,
+    };
+
+    #[test]
+    fn it_renders_into_a_larger_image() {
+        use crate::RenderError;
+
+        let identicon = Identicon::new(&HASH).size(64);
+        let mut atlas = RgbImage::from_pixel(200, 100, Rgb([1, 2, 3]));
+        identicon.render_into(&mut atlas, 120, 30).unwrap();
+
+        let icon = identicon.image();
+        for (x, y, pixel) in atlas.enumerate_pixels() {
+            let inside = (120..184).contains(&x) && (30..94).contains(&y);
+            if inside {
+                assert_eq!(icon.get_pixel(x - 120, y - 30), pixel);
+            } else {
+                assert_eq!(Rgb([1, 2, 3]), *pixel);
+            }
+        }
+
+        let err = identicon.render_into(&mut atlas, 137, 0).unwrap_err();
+        let expected = RenderError::OutOfBounds {
+            x: 137,
+            y: 0,
+            size: (64, 64),
+            width: 200,
+            height: 100,
+        };
+        match err {
+            IdenticonError::OutOfBounds(err) => assert_eq!(expected, err),
+            _ => panic!("expected OutOfBounds"),
+        }
+        assert!(identicon.render_into(&mut atlas, 0, 37).is_err());
+        assert!(identicon.render_into(&mut atlas, 0, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn it_wraps_a_dynamic_image() {
+        let identicon = Identicon::new(&HASH);
+        let image = identicon.dynamic_image();
+        assert_eq!(Some(&identicon.image()), image.as_rgb8());
+    }
+
+    #[test]
+    fn it_lays_out_sprite_sheets() {
+        use crate::sprite_sheet;
+
+        let mut other = HASH;
+        other.reverse();
+        let icons = [
+            Identicon::new(&HASH).size(64),
+            Identicon::new(&other).size(48),
+            Identicon::new(&HASH).size(64).background(Rgb([0, 0, 0])),
+        ];
+        let sheet = sprite_sheet(&icons, 2).unwrap();
+        assert_eq!((128, 128), sheet.dimensions());
+
+        let first = icons[0].image();
+        assert_eq!(first.get_pixel(0, 0), sheet.get_pixel(0, 0));
+        assert_eq!(first.get_pixel(63, 63), sheet.get_pixel(63, 63));
+        assert_eq!(
+            icons[1].image().get_pixel(47, 47),
+            sheet.get_pixel(64 + 47, 47)
+        );
+        assert_eq!(Rgb([240, 240, 240]), *sheet.get_pixel(64 + 48, 48));
+        assert_eq!(Rgb([0, 0, 0]), *sheet.get_pixel(0, 64));
+
+        assert_eq!((64, 192), sprite_sheet(&icons, 1).unwrap().dimensions());
+        assert_eq!((192, 64), sprite_sheet(&icons, 5).unwrap().dimensions());
+        assert_eq!((0, 0), sprite_sheet(&[], 3).unwrap().dimensions());
+
+        // Too wide to describe, so nothing gets allocated.
+        let huge = [
+            Identicon::new(&HASH).size(u32::MAX / 2),
+            Identicon::new(&HASH).size(u32::MAX / 2),
+            Identicon::new(&HASH).size(u32::MAX / 2),
+        ];
+        assert!(matches!(
+            sprite_sheet(&huge, 3),
+            Err(IdenticonError::OutOfRange {
+                option: "sprite sheet width",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn it_renders_empty_images() {
+        for identicon in [
+            Identicon::new(&HASH).size(0),
+            Identicon::new(&HASH).dimensions(0, 10),
+            Identicon::new(&HASH).dimensions(10, 0),
+            Identicon::new(&HASH).scale(0),
+        ] {
+            let (width, height) = identicon.bounds();
+            assert_eq!((width, height), identicon.image().dimensions());
+            assert_eq!((width, height), identicon.image_rgba().dimensions());
+            assert_eq!((width, height), identicon.image_luma().dimensions());
+            assert_eq!(
+                (width, height),
+                Renderer::new().render(&identicon).dimensions()
+            );
+            let mut buf = RgbImage::new(width, height);
+            identicon.render_into(&mut buf, 0, 0).unwrap();
+        }
+        let empty = crate::render_grid(&[true; 4], 2, 0, Rgb([0, 0, 0]), Rgb([255, 255, 255]));
+        assert_eq!((0, 0), empty.dimensions());
+        #[cfg(feature = "rayon")]
+        assert_eq!(
+            (0, 10),
+            Identicon::new(&HASH)
+                .dimensions(0, 10)
+                .image_parallel()
+                .dimensions()
+        );
+        // PNG has no empty images, so this is an error rather than a panic.
+        assert!(Identicon::new(&HASH).size(0).to_png_bytes().is_err());
+    }
+
+    #[test]
+    fn it_renders_plain_grids() {
+        use crate::render_grid;
+
+        let (fg, bg) = (Rgb([0, 0, 0]), Rgb([255, 255, 255]));
+        // Two 20px cells with a 10px margin around them.
+        let checker = render_grid(&[true, false, false, true], 2, 60, fg, bg);
+        assert_eq!((60, 60), checker.dimensions());
+        assert_eq!(bg, *checker.get_pixel(5, 5));
+        assert_eq!(fg, *checker.get_pixel(10, 10));
+        assert_eq!(fg, *checker.get_pixel(29, 29));
+        assert_eq!(bg, *checker.get_pixel(30, 10));
+        assert_eq!(bg, *checker.get_pixel(10, 30));
+        assert_eq!(fg, *checker.get_pixel(49, 49));
+        assert_eq!(bg, *checker.get_pixel(50, 50));
+
+        let identicon = Identicon::new(&HASH).size(64).foreground(fg).background(bg);
+        let grid = render_grid(&identicon.pixel_grid(), 5, 64, fg, bg);
+        assert_eq!(identicon.image(), grid);
+    }
+
+    #[test]
+    #[should_panic(expected = "a grid 2 cells wide needs 4 cells, got 3")]
+    fn it_rejects_ragged_grids() {
+        let _ = crate::render_grid(&[true; 3], 2, 60, Rgb([0, 0, 0]), Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    #[should_panic(expected = "a grid 70000 cells wide needs 4900000000 cells, got 3")]
+    fn it_rejects_grids_too_wide_to_count() {
+        let _ = crate::render_grid(&[true; 3], 70000, 60, Rgb([0, 0, 0]), Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    fn it_reuses_the_render_buffer() {
+        let mut renderer = Renderer::new();
+        let first = Identicon::new(&HASH);
+        assert_eq!(first.image(), *renderer.render(&first));
+        let buffer = renderer.render(&first).as_ptr();
+
+        let mut other = HASH;
+        other.reverse();
+        let second = Identicon::new(&other).cell_shape(CellShape::Circle);
+        assert_eq!(second.image(), *renderer.render(&second));
+        assert_eq!(buffer, renderer.render(&second).as_ptr());
+
+        let smaller = Identicon::new(&HASH).size(64).antialias(2);
+        assert_eq!(smaller.image(), *renderer.render(&smaller));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn it_renders_in_parallel() {
+        let identicon = Identicon::new(&HASH).size(301);
+        assert_eq!(identicon.image(), identicon.image_parallel());
+
+        let identicon = identicon.cell_shape(CellShape::Circle).two_tone(true);
+        assert_eq!(identicon.image(), identicon.image_parallel());
+
+        let identicon = identicon.antialias(3).stroke(3, Rgb([0, 0, 0]));
+        assert_eq!(identicon.image(), identicon.image_parallel());
+
+        let (a, b) = (Rgb([255, 255, 255]), Rgb([204, 204, 204]));
+        let identicon = identicon.background_fill(Background::Checker { a, b, cell: 7 });
+        assert_eq!(identicon.image(), identicon.image_parallel());
+    }
+
+    #[test]
+    fn it_scales_to_size() {
+        let full = Identicon::new(&HASH).image();
+        let half = Identicon::new(&HASH).size(210).image();
+        assert_eq!((420, 420), full.dimensions());
+        assert_eq!((210, 210), half.dimensions());
+
+        // Sample the middle of every cell in both images.
+        for row in 0..5 {
+            for col in 0..5 {
+                let big = full.get_pixel(35 + col * 70 + 35, 35 + row * 70 + 35);
+                let small = half.get_pixel(17 + col * 35 + 17, 17 + row * 35 + 17);
+                assert_eq!(big, small);
+            }
+        }
+    }
+
+    #[test]
+    fn it_renders_non_square_dimensions() {
+        let square = Identicon::new(&HASH).size(100);
+        assert_eq!(square.image(), square.clone().dimensions(100, 100).image());
+        assert_eq!(square.svg(), square.clone().dimensions(100, 100).svg());
+
+        let full = Identicon::new(&HASH).image();
+        let wide = Identicon::new(&HASH).dimensions(420, 210);
+        let image = wide.image();
+        assert_eq!((420, 210), image.dimensions());
+        assert_eq!((420, 210), wide.image_rgba().dimensions());
+        for row in 0..5 {
+            for col in 0..5 {
+                let big = full.get_pixel(35 + col * 70 + 35, 35 + row * 70 + 35);
+                let cell = image.get_pixel(35 + col * 70 + 35, 17 + row * 35 + 17);
+                assert_eq!(big, cell);
+            }
+        }
+        assert_eq!(image, wide.clone().size(64).dimensions(420, 210).image());
+        assert_eq!((64, 64), wide.clone().size(64).image().dimensions());
+
+        let mut sheet = RgbImage::new(500, 300);
+        wide.render_into(&mut sheet, 80, 90).unwrap();
+        assert!(wide.render_into(&mut sheet, 81, 91).is_err());
+
+        // Too short for a pixel per cell, so there's only the background.
+        let sliver = Identicon::new(&[0; 16]).dimensions(1000, 3).image();
+        assert!(sliver.pixels().all(|p| *p == Rgb([240, 240, 240])));
+        let sliver = Identicon::new(&[0; 16]).dimensions(3, 1000).antialias(2);
+        assert!(sliver.image().pixels().all(|p| *p == Rgb([240, 240, 240])));
+    }
+
+    #[test]
+    fn it_centers_uneven_sizes() {
+        // All-zero nibbles paint every cell, so the painted area is the whole grid.
+        let source = [0; 16];
+        for size in 100..=104 {
+            let image = Identicon::new(&source).size(size).image();
+            let background = *image.get_pixel(0, 0);
+            let painted: Vec<u32> = (0..size)
+                .filter(|&x| *image.get_pixel(x, size / 2) != background)
+                .collect();
+            let left = painted[0];
+            let right = size - 1 - painted[painted.len() - 1];
+            assert!(left.max(right) - left.min(right) <= 1, "size {}", size);
+
+            let painted: Vec<u32> = (0..size)
+                .filter(|&y| *image.get_pixel(size / 2, y) != background)
+                .collect();
+            let top = painted[0];
+            let bottom = size - 1 - painted[painted.len() - 1];
+            assert!(top.max(bottom) - top.min(bottom) <= 1, "size {}", size);
+        }
+    }
+
+    #[test]
+    fn it_encodes_png_bytes() {
+        let bytes = Identicon::new(&HASH).size(100).to_png_bytes().unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgb8();
+        assert_eq!((100, 100), image.dimensions());
+        assert_eq!(Identicon::new(&HASH).size(100).image(), image);
+    }
+
+    #[test]
+    fn it_exposes_raw_rgb() {
+        let identicon = Identicon::new(&HASH).size(30);
+        let (width, height, pixels) = identicon.to_rgb_raw();
+        assert_eq!((30, 30), (width, height));
+        assert_eq!(30 * 30 * 3, pixels.len());
+        assert_eq!(&[240, 240, 240], &pixels[..3]);
+        assert_eq!(identicon.image().into_raw(), pixels);
+    }
+
+    #[test]
+    fn it_reports_the_painted_color() {
+        let identicon = Identicon::new(&HASH);
+        let image = identicon.image();
+        let ix = identicon.pixel_grid().iter().position(|&p| p).unwrap() as u32;
+        let (x, y) = (70 + ix % 5 * 70, 70 + ix / 5 * 70);
+        assert_eq!(*image.get_pixel(x, y), identicon.foreground_color());
+        assert_eq!(Rgb([130, 214, 102]), identicon.foreground_color());
+
+        let fixed = Identicon::new(&HASH).foreground(Rgb([1, 2, 3]));
+        assert_eq!(Rgb([1, 2, 3]), fixed.foreground_color());
+    }
+
+    #[test]
+    fn it_renders_theme_pairs() {
+        let identicon = Identicon::new(&HASH).size(64);
+        let (light, dark) = identicon.theme_pair();
+        let expected = |theme| identicon.clone().theme(theme).ensure_contrast(3.0);
+        assert_eq!(expected(Theme::GitHubLight).image(), light);
+        assert_eq!(expected(Theme::GitHubDark).image(), dark);
+        assert_eq!(Rgb([240, 240, 240]), *light.get_pixel(0, 0));
+        assert_eq!(Rgb([13, 17, 23]), *dark.get_pixel(0, 0));
+        for theme in [Theme::GitHubLight, Theme::GitHubDark] {
+            assert!(expected(theme).contrast_ratio() >= 3.0);
+        }
+
+        // The same cells are painted in both.
+        let (fg_light, fg_dark) = (
+            expected(Theme::GitHubLight).foreground_color(),
+            expected(Theme::GitHubDark).foreground_color(),
+        );
+        for (l, d) in light.pixels().zip(dark.pixels()) {
+            assert_eq!(*l == fg_light, *d == fg_dark);
+        }
+
+        let strict = identicon.clone().ensure_contrast(7.0).theme_pair();
+        let expected = |theme| identicon.clone().theme(theme).ensure_contrast(7.0);
+        assert_eq!(expected(Theme::GitHubDark).image(), strict.1);
+    }
+
+    #[test]
+    fn it_crops_circles() {
+        let identicon = Identicon::new(&HASH).size(64);
+        let image = identicon.image();
+        let cropped = identicon.circle_crop();
+        assert_eq!((64, 64), cropped.dimensions());
+        for (x, y) in [(32, 32), (0, 32), (32, 63), (10, 32)] {
+            let Rgb([r, g, b]) = *image.get_pixel(x, y);
+            assert_eq!(Rgba([r, g, b, 255]), *cropped.get_pixel(x, y));
+        }
+        for (x, y) in [(0, 0), (63, 0), (0, 63), (63, 63), (8, 8)] {
+            assert_eq!(0, cropped.get_pixel(x, y)[3]);
+        }
+        assert!(cropped.pixels().all(|p| p[3] == 0 || p[3] == 255));
+
+        let smooth = identicon.antialias(4).circle_crop();
+        assert_eq!(255, smooth.get_pixel(32, 32)[3]);
+        assert_eq!(0, smooth.get_pixel(0, 0)[3]);
+        assert!(smooth.pixels().any(|p| p[3] > 0 && p[3] < 255));
+    }
+
+    #[test]
+    fn it_renders_16_bit_channels() {
+        // Every pixel rounds to what `image()` draws, give or take one where
+        // it was mixed, and the painted ones have more to them than 8 bits.
+        let matches = |identicon: Identicon| {
+            let (narrow, wide) = (identicon.image(), identicon.image_rgb16());
+            assert_eq!(narrow.dimensions(), wide.dimensions());
+            let background = *narrow.get_pixel(0, 0);
+            let mut painted = 0;
+            for (n, w) in narrow.pixels().zip(wide.pixels()) {
+                for c in 0..3 {
+                    let rounded = (w[c] as f32 / 257.0).round() as i32;
+                    assert!((rounded - n[c] as i32).abs() <= 1, "{:?} {:?}", n, w);
+                }
+                if *n != background {
+                    painted += 1;
+                    assert!(w.0.iter().any(|&c| c % 257 != 0), "{:?}", w);
+                }
+            }
+            assert!(painted > 0);
+        };
+        let identicon = Identicon::new(&HASH).size(64);
+        assert_eq!(
+            Rgb([240 * 257; 3]),
+            *identicon.image_rgb16().get_pixel(0, 0)
+        );
+        matches(identicon.clone());
+        matches(identicon.clone().ensure_contrast(7.0));
+        matches(identicon.clone().two_tone(true));
+        matches(identicon.clone().mode(Mode::Blockies));
+        matches(identicon.clone().cell_shape(CellShape::Circle).antialias(4));
+        matches(identicon.clone().opacity(0.5));
+
+        // A fixed color has nothing more to give.
+        let fixed = identicon.foreground(Rgb([10, 20, 30])).image_rgb16();
+        assert!(fixed.pixels().all(|p| p.0.iter().all(|&c| c % 257 == 0)));
+    }
+
+    #[test]
+    fn it_blends_in_linear_light() {
+        for value in 0..=255u8 {
+            assert_eq!(value, super::encode::<u8>(super::LINEAR[value as usize]));
+        }
+
+        let (black, white) = (Rgb([0, 0, 0]), Rgb([255, 255, 255]));
+        let plain = Identicon::from_mask(0x01ff_ffff, black, white, 64);
+        assert_eq!(plain.image(), plain.clone().linear_blending(false).image());
+        assert_eq!(plain.image(), plain.clone().linear_blending(true).image());
+
+        // Half of black over white is half as much light, not half the value.
+        let half = plain.clone().opacity(0.5);
+        let painted = |image: &RgbImage| *image.get_pixel(32, 32);
+        assert_eq!(Rgb([128, 128, 128]), painted(&half.image()));
+        assert_eq!(
+            Rgb([188, 188, 188]),
+            painted(&half.clone().linear_blending(true).image())
+        );
+        let tinted = plain.clone().tint(black, 0.5).linear_blending(true).image();
+        assert_eq!(Rgb([188, 188, 188]), *tinted.get_pixel(0, 0));
+
+        // The edges of antialiased circles come out lighter, and nothing else
+        // changes.
+        let circles = plain.cell_shape(CellShape::Circle).antialias(4);
+        let (srgb, linear) = (circles.image(), circles.linear_blending(true).image());
+        let mut lighter = 0;
+        for (a, b) in srgb.pixels().zip(linear.pixels()) {
+            assert!(b[0] >= a[0]);
+            assert_eq!((a[0] == 0 || a[0] == 255), a == b);
+            lighter += (b[0] > a[0]) as usize;
+        }
+        assert!(lighter > 0);
+    }
+
+    #[test]
+    fn it_tints_everything() {
+        let plain = Identicon::new(&HASH).size(64);
+        let sepia = Rgb([112, 66, 20]);
+        assert_eq!(plain.image(), plain.clone().tint(sepia, 0.0).image());
+        assert_eq!(plain.image(), plain.clone().tint(sepia, f32::NAN).image());
+
+        let full = plain.clone().tint(sepia, 1.0).image();
+        assert!(full.pixels().all(|p| *p == sepia));
+
+        let image = plain.image();
+        let tinted = plain.clone().tint(sepia, 0.25).image();
+        for (pixel, tinted) in image.pixels().zip(tinted.pixels()) {
+            for c in 0..3 {
+                let v = pixel[c] as f32;
+                let expected = (v + (sepia[c] as f32 - v) * 0.25).round() as u8;
+                assert_eq!(expected, tinted[c]);
+            }
+        }
+
+        // Applied after `adjust`, with transparency left alone.
+        let adjusted = plain.clone().adjust(-1.0, 1.0).tint(sepia, 0.5).image();
+        assert_eq!(Rgb([56, 33, 10]), *adjusted.get_pixel(0, 0));
+        let rgba = plain.clone().tint(sepia, 0.5).image_rgba();
+        assert_eq!(0, rgba.get_pixel(0, 0)[3]);
+    }
+
+    #[test]
+    fn it_adjusts_brightness_and_contrast() {
+        let plain = Identicon::new(&HASH).size(64);
+        assert_eq!(plain.image(), plain.clone().adjust(0.0, 1.0).image());
+        let nan = plain.clone().adjust(f32::NAN, f32::NAN);
+        assert_eq!(plain.image(), nan.image());
+
+        let darker = plain.clone().adjust(-0.1, 1.0).image();
+        let expected = |c: u8| ((c as f32 / 255.0 - 0.1) * 255.0).round() as u8;
+        for (d, p) in darker.pixels().zip(plain.image().pixels()) {
+            assert_eq!(Rgb(p.0.map(expected)), *d);
+        }
+
+        let flat = plain.clone().adjust(0.0, 0.0).image();
+        assert!(flat.pixels().all(|p| *p == Rgb([128, 128, 128])));
+
+        // Every raster path gets the same pass.
+        let adjusted = plain.clone().adjust(0.05, 1.5);
+        let mut renderer = Renderer::new();
+        assert_eq!(adjusted.image(), *renderer.render(&adjusted));
+        // Alpha is left alone.
+        assert_eq!(0, adjusted.image_rgba().get_pixel(0, 0)[3]);
+    }
+
+    #[test]
+    fn it_blends_with_opacity() {
+        let solid = Identicon::new(&HASH).size(64);
+        assert_eq!(solid.image(), solid.clone().opacity(1.0).image());
+
+        let ix = solid.pixel_grid().iter().position(|&p| p).unwrap() as u32;
+        let (x, y) = (11 + ix % 5 * 10, 11 + ix / 5 * 10);
+        let Rgb([r, g, b]) = solid.foreground_color();
+        let half = solid.clone().opacity(0.5).image();
+        let mix = |c: u8| ((c as f32 + 240.0) / 2.0).round() as u8;
+        assert_eq!(Rgb([mix(r), mix(g), mix(b)]), *half.get_pixel(x, y));
+        assert_eq!(Rgb([240, 240, 240]), *half.get_pixel(0, 0));
+
+        let clear = solid.clone().opacity(0.0).image();
+        assert!(clear.pixels().all(|p| *p == Rgb([240, 240, 240])));
+
+        // Over a transparent background the color stays, and alpha drops.
+        let rgba = solid.clone().opacity(0.5).image_rgba();
+        assert_eq!(Rgba([r, g, b, 128]), *rgba.get_pixel(x, y));
+
+        let checker = Background::Checker {
+            a: Rgb([0, 0, 0]),
+            b: Rgb([255, 255, 255]),
+            cell: 1,
+        };
+        let over = solid
+            .clone()
+            .foreground(Rgb([255, 255, 255]))
+            .background_fill(checker)
+            .opacity(0.5)
+            .image();
+        let (x, y) = (x & !1, y & !1);
+        assert_eq!(Rgb([128, 128, 128]), *over.get_pixel(x, y));
+        assert_eq!(Rgb([255, 255, 255]), *over.get_pixel(x + 1, y));
+    }
+
+    #[test]
+    fn it_renders_one_bit_images() {
+        let identicon = Identicon::new(&HASH)
+            .size(64)
+            .cell_shape(CellShape::Circle)
+            .antialias(4)
+            .stroke(2, Rgb([200, 0, 0]));
+        let luma = identicon.image_luma();
+        assert_eq!((64, 64), luma.dimensions());
+        assert!(luma.pixels().all(|p| p.0 == [0] || p.0 == [255]));
+
+        // Painted wherever the color image is, without the smoothing.
+        let plain = Identicon::new(&HASH).size(64).image();
+        let background = *plain.get_pixel(0, 0);
+        for (l, p) in Identicon::new(&HASH)
+            .size(64)
+            .image_luma()
+            .pixels()
+            .zip(plain.pixels())
+        {
+            assert_eq!(*p == background, l.0 == [255]);
+        }
+
+        // A frame is drawn like a stroke, whatever its color.
+        let framed = Identicon::new(&HASH)
+            .size(64)
+            .frame(3, Rgb([255, 255, 255]))
+            .image_luma();
+        assert_eq!([0], framed.get_pixel(0, 0).0);
+        assert_eq!([0], framed.get_pixel(63, 2).0);
+        assert_eq!([255], framed.get_pixel(3, 3).0);
+    }
+
+    #[cfg(feature = "jpeg")]
+    #[test]
+    fn it_encodes_jpeg_bytes() {
+        let identicon = Identicon::new(&HASH).size(100);
+        let bytes = identicon.to_jpeg_bytes(90).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgb8();
+        assert_eq!((100, 100), image.dimensions());
+
+        // Close to the original in the middle of a flat area, if not exact.
+        let expected = identicon.image();
+        let (a, b) = (image.get_pixel(2, 2), expected.get_pixel(2, 2));
+        assert!(a
+            .0
+            .iter()
+            .zip(b.0)
+            .all(|(&x, y)| (x as i32 - y as i32).abs() <= 4));
+
+        let worst = identicon.to_jpeg_bytes(0).unwrap();
+        assert_eq!(worst, identicon.to_jpeg_bytes(1).unwrap());
+        assert!(worst.len() < bytes.len());
+    }
+
+    #[cfg(feature = "webp")]
+    #[test]
+    fn it_encodes_webp_bytes() {
+        use image_webp::WebPDecoder;
+
+        let identicon = Identicon::new(&HASH).size(100);
+        let bytes = identicon.to_webp_bytes().unwrap();
+        assert!(bytes.len() < identicon.to_png_bytes().unwrap().len());
+
+        let mut decoder = WebPDecoder::new(io::Cursor::new(&bytes)).unwrap();
+        assert_eq!((100, 100), decoder.dimensions());
+        let mut pixels = vec![0; decoder.output_buffer_size().unwrap()];
+        decoder.read_image(&mut pixels).unwrap();
+        assert_eq!(identicon.image().into_raw(), pixels);
+    }
+
+    #[cfg(feature = "ico")]
+    #[test]
+    fn it_encodes_ico() {
+        let identicon = Identicon::new(&HASH);
+        let bytes = identicon.to_ico(&[16, 32, 256]).unwrap();
+        assert_eq!(&[0, 0, 1, 0, 3, 0], &bytes[..6]);
+        assert_eq!(&[16, 16], &bytes[6..8]);
+        assert_eq!(&[0, 0], &bytes[38..40]);
+
+        // The decoder picks the largest image.
+        let decoded = image::load_from_memory_with_format(&bytes, image::ImageFormat::Ico);
+        assert_eq!(
+            identicon.size(256).image_rgba(),
+            decoded.unwrap().to_rgba8()
+        );
+
+        let identicon = Identicon::new(&HASH);
+        assert!(identicon.to_ico(&[]).is_err());
+        assert!(identicon.to_ico(&[0]).is_err());
+        let err = identicon.to_ico(&[16, 257]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("ICO sizes must be from 1 to 256, got 257"));
+        assert!(identicon.to_ico(&[16; 17]).is_err());
+    }
+
+    #[test]
+    fn it_saves_to_a_file() {
+        let path = std::env::temp_dir().join(format!("identicon-{}.png", std::process::id()));
+        let identicon = Identicon::new(&HASH).size(64);
+        identicon.save(&path).unwrap();
+        let saved = image::open(&path).unwrap().to_rgb8();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(identicon.image(), saved);
+
+        let unknown = std::env::temp_dir().join("identicon.unknown");
+        assert!(matches!(
+            identicon.save(unknown),
+            Err(IdenticonError::Encode(_))
+        ));
+    }
+
+    #[test]
+    fn it_streams_png_into_writers() {
+        let identicon = Identicon::new(&HASH).size(100);
+        let mut cursor = io::Cursor::new(Vec::new());
+        identicon.to_png(&mut cursor).unwrap();
+        let image = image::load_from_memory(cursor.get_ref()).unwrap().to_rgb8();
+        assert_eq!(identicon.image(), image);
+    }
+
+    #[test]
+    fn it_reports_encoding_errors() {
+        struct Full;
+
+        impl io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::WriteZero, "full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let err = Identicon::new(&HASH).to_png(&mut Full).unwrap_err();
+        assert!(matches!(err, IdenticonError::Encode(_)));
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(err.to_string().starts_with("could not encode image"));
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn it_encodes_animated_gif() {
+        use image::codecs::gif::GifDecoder;
+        use image::{AnimationDecoder, Pixel};
+
+        let identicon = Identicon::new(&HASH).size(105);
+        let gif = identicon.to_gif(4).unwrap();
+        let decoder = GifDecoder::new(gif.as_slice()).unwrap();
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(4, frames.len());
+
+        let still = identicon.image();
+        let pixels = identicon.pixels();
+        let ix = pixels.iter().position(|p| *p).unwrap() as u32;
+        // 17px cells, starting 10px in.
+        let (x, y) = (10 + ix % 5 * 17 + 8, 10 + ix / 5 * 17 + 8);
+        let first = frames[0].buffer().get_pixel(x, y).to_rgb();
+        assert_eq!(*still.get_pixel(x, y), first);
+        for frame in &frames[1..] {
+            assert_eq!(
+                still.get_pixel(0, 0).to_rgba(),
+                *frame.buffer().get_pixel(0, 0)
+            );
+            assert_ne!(first, frame.buffer().get_pixel(x, y).to_rgb());
+        }
+    }
+
+    #[cfg(feature = "data-uri")]
+    #[test]
+    fn it_encodes_data_uri() {
+        use base64::Engine;
+
+        let uri = Identicon::new(&HASH).data_uri().unwrap();
+        let payload = uri.strip_prefix("data:image/png;base64,").unwrap();
+        let png = base64::engine::general_purpose::STANDARD
+            .decode(payload)
+            .unwrap();
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!((420, 420), image.to_rgb8().dimensions());
+    }
+
+    #[test]
+    fn it_renders_transparent_background() {
+        let image = Identicon::new(&HASH).image_rgba();
+        for &(x, y) in &[(0, 0), (419, 0), (0, 419), (419, 419)] {
+            assert_eq!(0, image.get_pixel(x, y)[3]);
+        }
+
+        let pixels = Identicon::new(&HASH).pixels();
+        let ix = pixels.iter().position(|p| *p).unwrap() as u32;
+        let (row, col) = (ix / 5, ix % 5);
+        let pixel = image.get_pixel(35 + col * 70 + 35, 35 + row * 70 + 35);
+        assert_eq!(255, pixel[3]);
+    }
+
+    #[test]
+    fn it_generates_larger_grids() {
+        for &n in &[7, 9] {
+            let pixels = Identicon::new(&HASH).grid_size(n).pixels();
+            let n = n as usize;
+            assert_eq!(n * n, pixels.len());
+            for row in 0..n {
+                for col in 0..n {
+                    assert_eq!(pixels[row * n + col], pixels[row * n + n - 1 - col]);
+                }
+            }
+        }
+
+        let default = Identicon::new(&HASH).image();
+        assert_eq!(default, Identicon::new(&HASH).grid_size(5).image());
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn it_hashes_arbitrary_input() {
+        let long = [7; 1024];
+        for input in &[&[][..], &long[..]] {
+            let image = Identicon::from_bytes(input).image();
+            assert_eq!(image, Identicon::from_bytes(input).image());
+        }
+        assert_ne!(
+            Identicon::from_bytes(&[]).image(),
+            Identicon::from_bytes(&long).image()
+        );
+    }
+
+    #[test]
+    fn it_rounds_corners() {
+        // All-zero nibbles paint every cell.
+        let source = [0; 16];
+        let square = Identicon::new(&source).image();
+        assert_eq!(square, Identicon::new(&source).corner_radius(0).image());
+
+        let background = Rgb([240, 240, 240]);
+        for &radius in &[10, 35, 1000] {
+            let image = Identicon::new(&source).corner_radius(radius).image();
+            assert_eq!(background, *image.get_pixel(35, 35));
+            assert_eq!(background, *image.get_pixel(104, 104));
+            assert_ne!(background, *image.get_pixel(70, 70));
+            assert_ne!(background, *image.get_pixel(70, 35));
+        }
+
+        // An oversized radius clamps to a circle at any supersampling.
+        let huge = Identicon::new(&source).corner_radius(u32::MAX).antialias(4);
+        let circle = Identicon::new(&source).corner_radius(35).antialias(4);
+        assert_eq!(circle.image(), huge.image());
+    }
+
+    #[test]
+    fn it_saturates_huge_strokes() {
+        // A stroke wider than the image covers all of it at any supersampling.
+        let ink = Rgb([0, 0, 0]);
+        for &(width, samples) in &[(u32::MAX, 2), (2_000_000_000, 4)] {
+            let image = Identicon::new(&HASH)
+                .size(32)
+                .stroke(width, ink)
+                .antialias(samples)
+                .image();
+            assert!(image.pixels().all(|p| *p == ink));
+        }
+    }
+
+    #[test]
+    fn it_paints_circles() {
+        let source = [0; 16];
+        let image = Identicon::new(&source)
+            .cell_shape(CellShape::Circle)
+            .image();
+        let background = Rgb([240, 240, 240]);
+        for row in 0..5 {
+            for col in 0..5 {
+                let (x0, y0) = (35 + col * 70, 35 + row * 70);
+                assert_ne!(background, *image.get_pixel(x0 + 35, y0 + 35));
+                assert_eq!(background, *image.get_pixel(x0, y0));
+                assert_eq!(background, *image.get_pixel(x0 + 69, y0));
+                assert_eq!(background, *image.get_pixel(x0, y0 + 69));
+                assert_eq!(background, *image.get_pixel(x0 + 69, y0 + 69));
+            }
+        }
+    }
+
+    #[test]
+    fn it_paints_hexagons() {
+        let source = [0; 16];
+        let image = Identicon::new(&source)
+            .cell_shape(CellShape::Hexagon)
+            .image();
+        let background = Rgb([240, 240, 240]);
+        for row in 0..5 {
+            for col in 0..5 {
+                let (x0, y0) = (35 + col * 70, 35 + row * 70);
+                // The center and the points at the top and bottom.
+                assert_ne!(background, *image.get_pixel(x0 + 35, y0 + 35));
+                assert_ne!(background, *image.get_pixel(x0 + 35, y0 + 1));
+                assert_ne!(background, *image.get_pixel(x0 + 35, y0 + 68));
+                // The corners, and the sides past the flat edges.
+                assert_eq!(background, *image.get_pixel(x0, y0));
+                assert_eq!(background, *image.get_pixel(x0 + 69, y0 + 69));
+                assert_eq!(background, *image.get_pixel(x0 + 2, y0 + 35));
+                assert_eq!(background, *image.get_pixel(x0 + 67, y0 + 35));
+            }
+        }
+    }
+
+    #[test]
+    fn it_paints_hashed_shapes() {
+        let hashed = Identicon::new(&HASH).cell_shape(CellShape::Hashed);
+        let shapes = hashed.cell_shapes();
+        assert_eq!(shapes, hashed.clone().cell_shapes());
+        for row in shapes.chunks(5) {
+            assert_eq!((row[0], row[1]), (row[4], row[3]));
+        }
+        assert!(shapes.iter().any(|&shape| shape != shapes[0]));
+        assert!(!shapes.contains(&CellShape::Hashed));
+
+        // Each painted cell looks like the shape it was given.
+        let image = hashed.image();
+        let background = Rgb([240, 240, 240]);
+        for (ix, &painted) in hashed.pixel_grid().iter().enumerate() {
+            let (x0, y0) = (35 + ix as u32 % 5 * 70, 35 + ix as u32 / 5 * 70);
+            let expected = Identicon::new(&HASH).cell_shape(shapes[ix]).image();
+            for (dx, dy) in [(35, 35), (0, 0), (35, 1), (1, 35), (69, 69)] {
+                let (x, y) = (x0 + dx, y0 + dy);
+                assert_eq!(expected.get_pixel(x, y), image.get_pixel(x, y));
+            }
+            assert_eq!(painted, *image.get_pixel(x0 + 35, y0 + 35) != background);
+        }
+    }
+
+    #[test]
+    fn it_paints_triangles_and_diamonds() {
+        let source = [0; 16];
+        let background = Rgb([240, 240, 240]);
+        let triangle = Identicon::new(&source)
+            .cell_shape(CellShape::Triangle)
+            .image();
+        let diamond = Identicon::new(&source)
+            .cell_shape(CellShape::Diamond)
+            .image();
+        let (x0, y0) = (35, 35);
+        for image in [&triangle, &diamond] {
+            assert_ne!(background, *image.get_pixel(x0 + 35, y0 + 35));
+            assert_ne!(background, *image.get_pixel(x0 + 35, y0 + 1));
+            assert_eq!(background, *image.get_pixel(x0, y0));
+            assert_eq!(background, *image.get_pixel(x0 + 69, y0));
+        }
+        // The triangle's base spans the bottom, where the diamond is a point.
+        assert_ne!(background, *triangle.get_pixel(x0 + 1, y0 + 69));
+        assert_eq!(background, *diamond.get_pixel(x0 + 1, y0 + 69));
+        assert_ne!(background, *diamond.get_pixel(x0 + 1, y0 + 35));
+        assert_eq!(background, *triangle.get_pixel(x0 + 1, y0 + 35));
+    }
+
+    #[test]
+    fn it_fills_gradients() {
+        let source = [0; 16];
+        let (start, end) = (Rgb([0, 0, 0]), Rgb([200, 100, 40]));
+        let image = Identicon::new(&source)
+            .foreground_fill(Foreground::Gradient { start, end })
+            .image();
+        let colors: Vec<Rgb<u8>> = (0..5)
+            .map(|row| *image.get_pixel(70, 35 + row * 70 + 35))
+            .collect();
+        assert_eq!(start, colors[0]);
+        assert_eq!(Rgb([100, 50, 20]), colors[2]);
+        assert_eq!(end, colors[4]);
+    }
+
+    #[test]
+    fn it_exposes_the_pixel_grid() {
+        let identicon = Identicon::new(&HASH);
+        let grid = identicon.pixel_grid();
+        assert_eq!(25, grid.len());
+
+        let image = identicon.image();
+        let background = Rgb([240, 240, 240]);
+        for (ix, painted) in grid.iter().enumerate() {
+            let (row, col) = (ix as u32 / 5, ix as u32 % 5);
+            let pixel = *image.get_pixel(35 + col * 70 + 35, 35 + row * 70 + 35);
+            assert_eq!(*painted, pixel != background);
+        }
+    }
+
+    #[test]
+    fn it_strokes_cells() {
+        let ink = Rgb([0, 0, 0]);
+        let unstroked = Identicon::new(&HASH).stroke(0, ink).image();
+        assert_eq!(Identicon::new(&HASH).image(), unstroked);
+
+        let fill = Rgb([200, 0, 0]);
+        let grid = Identicon::new(&HASH).pixel_grid();
+        let image = Identicon::new(&HASH)
+            .foreground(fill)
+            .stroke(4, ink)
+            .image();
+
+        // Find two painted cells side by side; their shared edge is one line.
+        let ix = (0..25)
+            .find(|&ix| ix % 5 < 4 && grid[ix] && grid[ix + 1])
+            .expect("adjacent cells");
+        let (row, col) = (ix as u32 / 5, ix as u32 % 5);
+        let (edge, y) = (35 + (col + 1) * 70, 35 + row * 70 + 35);
+        assert_eq!(fill, *image.get_pixel(edge - 3, y));
+        for x in edge - 2..edge + 2 {
+            assert_eq!(ink, *image.get_pixel(x, y));
+        }
+        assert_eq!(fill, *image.get_pixel(edge + 2, y));
+
+        // Every other edge is outlined the same way.
+        assert_eq!(ink, *image.get_pixel(35 + col * 70 - 2, y));
+        assert_eq!(fill, *image.get_pixel(35 + col * 70 + 35, y));
+    }
+
+    #[test]
+    fn it_renders_inverted_cells() {
+        let background = Rgb([240, 240, 240]);
+        let mask = |image: &RgbImage| -> Vec<bool> {
+            (0..25)
+                .map(|ix| *image.get_pixel(35 + ix % 5 * 70 + 35, 35 + ix / 5 * 70 + 35))
+                .map(|pixel| pixel != background)
+                .collect()
+        };
+        let normal = mask(&Identicon::new(&HASH).image());
+        let inverted = mask(&Identicon::new(&HASH).invert().image());
+        assert_eq!(normal.iter().map(|p| !p).collect::<Vec<_>>(), inverted);
+    }
+
+    #[test]
+    fn it_antialiases() {
+        let plain = Identicon::new(&HASH).image();
+        assert_eq!(plain, Identicon::new(&HASH).antialias(1).image());
+        assert_eq!(plain, Identicon::new(&HASH).antialias(4).image());
+
+        let circles = Identicon::new(&HASH).cell_shape(CellShape::Circle);
+        let jagged = circles.image();
+        let circles = circles.antialias(4);
+        let smooth = circles.image();
+        assert_ne!(jagged, smooth);
+        assert_eq!(smooth, circles.antialias(9).image());
+
+        // Edge pixels blend the foreground into the background, while the
+        // middle of a painted cell stays solid.
+        let colors: std::collections::HashSet<_> = smooth.pixels().collect();
+        assert!(colors.len() > 2);
+        let grid = Identicon::new(&HASH).pixel_grid();
+        let ix = grid.iter().position(|&painted| painted).unwrap() as u32;
+        let (x, y) = (35 + ix % 5 * 70 + 35, 35 + ix / 5 * 70 + 35);
+        assert_eq!(jagged.get_pixel(x, y), smooth.get_pixel(x, y));
+    }
+
+    #[test]
+    fn it_antialiases_against_transparency() {
+        let fill = Rgb([200, 0, 0]);
+        let image = Identicon::new(&HASH)
+            .foreground(fill)
+            .cell_shape(CellShape::Circle)
+            .antialias(2)
+            .image_rgba();
+
+        // Partly covered pixels fade out rather than picking up the
+        // background's color.
+        let edges: Vec<_> = image.pixels().filter(|p| p[3] > 0 && p[3] < 255).collect();
+        assert!(!edges.is_empty());
+        for pixel in edges {
+            assert_eq!(fill.0, [pixel[0], pixel[1], pixel[2]]);
+        }
+    }
+
+    #[test]
+    fn it_uses_custom_margin() {
+        let default = Identicon::new(&HASH).image();
+        let explicit = Identicon::new(&HASH).margin(35).image();
+        assert_eq!(default, explicit);
+
+        // All-zero nibbles paint every cell, so a zero margin reaches every edge.
+        let source = [0; 16];
+        let image = Identicon::new(&source).margin(0).image();
+        let background = Rgb([240, 240, 240]);
+        for i in 0..420 {
+            assert_ne!(background, *image.get_pixel(i, 0));
+            assert_ne!(background, *image.get_pixel(0, i));
+            assert_ne!(background, *image.get_pixel(i, 419));
+            assert_ne!(background, *image.get_pixel(419, i));
+        }
+    }
+
+    #[test]
+    fn it_uses_margin_ratios() {
+        // 1/12 is the default's half cell at 420px.
+        let default = Identicon::new(&HASH).image();
+        assert_eq!(
+            default,
+            Identicon::new(&HASH).margin_ratio(1.0 / 12.0).image()
+        );
+
+        // The same share of any size.
+        let tenth = |size| Identicon::new(&[0; 16]).size(size).margin_ratio(0.1);
+        let background = Rgb([240, 240, 240]);
+        for size in [100, 200, 500] {
+            let image = tenth(size).image();
+            let margin = size / 10;
+            assert_eq!(background, *image.get_pixel(margin - 1, margin));
+            assert_ne!(background, *image.get_pixel(margin, margin));
+            assert_eq!(image, tenth(size).margin(margin).image());
+        }
+
+        // The last of `margin` and `margin_ratio` wins.
+        let absolute = Identicon::new(&HASH).margin_ratio(0.2).margin(35).image();
+        assert_eq!(default, absolute);
+        let ratio = Identicon::new(&HASH)
+            .margin(0)
+            .margin_ratio(1.0 / 12.0)
+            .image();
+        assert_eq!(default, ratio);
+        let clamped = Identicon::new(&HASH).margin_ratio(2.0).image();
+        assert!(clamped.pixels().all(|p| *p == background));
+    }
+
+    #[test]
+    fn it_uses_custom_background() {
+        let dark = Rgb([20, 20, 20]);
+        let image = Identicon::new(&HASH).background(dark).image();
+        assert_eq!(dark, *image.get_pixel(0, 0));
+
+        // Every unpainted cell shows the background too.
+        let pixels = Identicon::new(&HASH).pixels();
+        for (ix, painted) in pixels.iter().enumerate() {
+            let (row, col) = (ix as u32 / 5, ix as u32 % 5);
+            let pixel = *image.get_pixel(35 + col * 70 + 35, 35 + row * 70 + 35);
+            assert_eq!(!painted, pixel == dark);
+        }
+    }
+
+    #[test]
+    fn it_draws_frames() {
+        let red = Rgb([255, 0, 0]);
+        let identicon = Identicon::new(&HASH).size(64).margin(0);
+        let image = identicon.clone().frame(3, red).image();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let edge = x < 3 || y < 3 || x >= 61 || y >= 61;
+            assert_eq!(edge, *pixel == red, "({}, {})", x, y);
+        }
+        assert_eq!(identicon.image(), identicon.clone().frame(0, red).image());
+
+        let rgba = identicon.clone().antialias(4).frame(3, red).image_rgba();
+        assert_eq!(Rgba([255, 0, 0, 255]), *rgba.get_pixel(63, 63));
+
+        let wide = identicon.frame(100, red).image();
+        assert!(wide.pixels().all(|p| *p == red));
+    }
+
+    #[test]
+    fn it_draws_gradient_backgrounds() {
+        let (start, end) = (Rgb([0, 0, 0]), Rgb([200, 100, 0]));
+        let gradient = |vertical| Background::LinearGradient {
+            start,
+            end,
+            vertical,
+        };
+        let identicon = Identicon::new(&HASH).size(101);
+
+        let image = identicon.clone().background_fill(gradient(true)).image();
+        assert_eq!(start, *image.get_pixel(0, 0));
+        assert_eq!(start, *image.get_pixel(100, 0));
+        assert_eq!(Rgb([100, 50, 0]), *image.get_pixel(0, 50));
+        assert_eq!(end, *image.get_pixel(100, 100));
+
+        let image = identicon.background_fill(gradient(false)).image();
+        assert_eq!(start, *image.get_pixel(0, 0));
+        assert_eq!(Rgb([100, 50, 0]), *image.get_pixel(100, 0));
+        assert_eq!(Rgb([100, 50, 0]), *image.get_pixel(0, 100));
+        assert_eq!(end, *image.get_pixel(100, 100));
+    }
+
+    #[test]
+    fn it_draws_checkered_backgrounds() {
+        let (a, b) = (Rgb([255, 255, 255]), Rgb([204, 204, 204]));
+        let checker = Background::Checker { a, b, cell: 8 };
+        let image = Identicon::new(&HASH).background_fill(checker).image();
+        assert_eq!(a, *image.get_pixel(0, 0));
+        assert_eq!(a, *image.get_pixel(7, 7));
+        assert_eq!(b, *image.get_pixel(8, 0));
+        assert_eq!(b, *image.get_pixel(0, 8));
+        assert_eq!(a, *image.get_pixel(8, 8));
+
+        assert_eq!(a, *image.get_pixel(419, 0));
+        assert_eq!(b, *image.get_pixel(419, 411));
+
+        let pixels = Identicon::new(&HASH).pixels();
+        let ix = pixels.iter().position(|p| *p).unwrap() as u32;
+        let (row, col) = (ix / 5, ix % 5);
+        let pixel = *image.get_pixel(35 + col * 70 + 35, 35 + row * 70 + 35);
+        assert!(pixel != a && pixel != b);
+    }
+
+    #[test]
+    fn it_uses_custom_foreground() {
+        let color = Rgb([10, 20, 30]);
+        let image = Identicon::new(&HASH).foreground(color).image();
+        let pixels = Identicon::new(&HASH).pixels();
+        for (ix, painted) in pixels.iter().enumerate() {
+            let (row, col) = (ix as u32 / 5, ix as u32 % 5);
+            let pixel = *image.get_pixel(35 + col * 70 + 35, 35 + row * 70 + 35);
+            assert_eq!(*painted, pixel == color);
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Without `std` the `image` crate isn't available, so this stands in for its
+//! `Rgb` and `Rgba` types. They have the same shape, so `Rgb([r, g, b])` works
+//! either way.
+
+/// An RGB color.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgb<T>(pub [T; 3]);
+
+/// An RGB color with an alpha channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgba<T>(pub [T; 4]);
@@ -0,0 +1,13 @@
+//! (De)serializes `Rgb<u8>` as a `[r, g, b]` array, since `image` doesn't
+//! implement serde's traits itself.
+
+use crate::Rgb;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<S: Serializer>(color: &Rgb<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+    color.0.serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rgb<u8>, D::Error> {
+    <[u8; 3]>::deserialize(deserializer).map(Rgb)
+}
@@ -0,0 +1,28 @@
+//! Bindings for JavaScript through `wasm-bindgen`, for use in the browser.
+
+use wasm_bindgen::prelude::*;
+
+use crate::Identicon;
+
+/// Render the identicon for `source` as PNG bytes, `size` pixels square.
+///
+/// From JavaScript this is `renderPng(source: Uint8Array, size: number)`, and
+/// it throws if `source` is too short.
+#[wasm_bindgen(js_name = renderPng)]
+pub fn render_png(source: &[u8], size: u32) -> Result<Vec<u8>, JsError> {
+    let identicon = Identicon::try_new(source)?.size(size);
+    Ok(identicon.to_png_bytes()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_png;
+    use crate::tests::HASH;
+
+    #[test]
+    fn it_renders_png_bytes() {
+        let png = render_png(&HASH, 64).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_rgb8();
+        assert_eq!((64, 64), image.dimensions());
+    }
+}